@@ -0,0 +1,131 @@
+use anyhow::{Result, bail};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::texture_atlas::TextureDatabase;
+
+/// How many times a second the game updates
+pub const TICKS_PER_SECOND: u32 = 60;
+
+fn default_frame_ticks() -> u32 {
+    TICKS_PER_SECOND / 8
+}
+
+/// A tile that cycles through a run of frames next to each other in its tileset
+#[derive(Clone, Debug, Deserialize)]
+pub struct TileAnimation {
+    /// File name of the tileset's atlas
+    pub tileset: String,
+    /// Frame the animation starts on
+    pub tile_id: i32,
+    /// How many frames the animation has, starting at `tile_id`
+    pub frames: i32,
+    /// How many game ticks each frame is shown for
+    #[serde(default = "default_frame_ticks")]
+    pub frame_ticks: u32,
+}
+
+impl TileAnimation {
+    /// Frame a tile placed as `tile_id` shows at a given tick
+    ///
+    /// Tiles can be placed as any frame of the animation, and keep their offset from the other tiles.
+    pub fn frame_at(&self, tile_id: i32, tick: u64) -> i32 {
+        let offset = (tile_id - self.tile_id) as u64 + tick / self.frame_ticks as u64;
+        self.tile_id + (offset % self.frames as u64) as i32
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnimationsFile {
+    #[serde(rename = "tile", default)]
+    tiles: Vec<TileAnimation>,
+}
+
+/// Every animated tile, keyed by tileset and each of its frames
+///
+/// The level files only store which frame a tile starts on, so how tiles animate comes from the frame runs in the
+/// texture database, see [`Animations::from_texture_database`], and optionally a table that adds to or replaces them.
+#[derive(Clone, Debug, Default)]
+pub struct Animations {
+    /// Keyed by tileset, then frame
+    tiles: HashMap<String, HashMap<i32, TileAnimation>>,
+}
+
+impl Animations {
+    /// Animations from the frame runs of every atlas
+    ///
+    /// An atlas with a single run is a sheet of separate frames, like a tileset. In an atlas with more than one, each run
+    /// longer than a frame is an animation that cycles through its frames at the table's default speed.
+    pub fn from_texture_database(texture_database: &TextureDatabase) -> Animations {
+        let mut animations = Animations::default();
+        for atlas in &texture_database.atlases {
+            if atlas.frames.len() < 2 {
+                continue;
+            }
+            for run in &atlas.frames {
+                if run.count > 1 {
+                    animations.insert(TileAnimation {
+                        tileset: atlas.file_name.clone(),
+                        tile_id: run.start_index,
+                        frames: run.count,
+                        frame_ticks: default_frame_ticks(),
+                    });
+                }
+            }
+        }
+        animations
+    }
+
+    /// Loads the table of animated tiles from a TOML file with a `[[tile]]` entry per animation
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Animations> {
+        let file: AnimationsFile = toml::from_str(&fs::read_to_string(path)?)?;
+
+        let mut animations = Animations::default();
+        for animation in file.tiles {
+            if animation.frames <= 0 || animation.frame_ticks == 0 {
+                bail!(
+                    "animation of tile {} in {} needs at least one frame and tick per frame",
+                    animation.tile_id,
+                    animation.tileset
+                );
+            }
+            animations.insert(animation);
+        }
+        Ok(animations)
+    }
+
+    fn insert(&mut self, animation: TileAnimation) {
+        let tileset = self.tiles.entry(animation.tileset.clone()).or_default();
+        for frame in animation.tile_id..animation.tile_id + animation.frames {
+            tileset.insert(frame, animation.clone());
+        }
+    }
+
+    /// Adds every animation from `other`, replacing any of these that share a frame with them
+    pub fn extend(&mut self, other: Animations) {
+        for (tileset, tiles) in other.tiles {
+            self.tiles.entry(tileset).or_default().extend(tiles);
+        }
+    }
+
+    /// Whether a tile changes frame over time
+    pub fn is_animated(&self, tileset: &str, tile_id: i32) -> bool {
+        self.tiles
+            .get(tileset)
+            .is_some_and(|tiles| tiles.contains_key(&tile_id))
+    }
+
+    /// Frame to draw for a tile at a given tick, which is the tile's own ID if it isn't animated
+    pub fn tile_frame(&self, tileset: &str, tile_id: i32, tick: u64) -> i32 {
+        match self
+            .tiles
+            .get(tileset)
+            .and_then(|tiles| tiles.get(&tile_id))
+        {
+            Some(animation) => animation.frame_at(tile_id, tick),
+            None => tile_id,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::collision::{Collision, CollisionTable};
+use crate::level::{Category, Room};
+
+/// Letter an object is drawn as, the same one the viewer labels it with
+pub fn object_symbol(category: Category) -> char {
+    match category {
+        Category::None => '?',
+        Category::Event(_) => 'E',
+        Category::Enemy(_) => 'M',
+        Category::Item(_) => 'I',
+    }
+}
+
+/// ANSI colour code of an object's letter
+fn object_color(category: Category) -> &'static str {
+    match category {
+        Category::None => "1;90",
+        Category::Event(_) => "1;93",
+        Category::Enemy(_) => "1;91",
+        Category::Item(_) => "1;92",
+    }
+}
+
+/// ANSI colour code of a tile drawn by how it collides
+fn collision_color(collision: Collision) -> &'static str {
+    match collision {
+        Collision::Empty => "90",
+        Collision::Solid => "34",
+        Collision::OneWay => "36",
+        Collision::Slope => "35",
+        Collision::Hazard => "31",
+        Collision::Breakable => "33",
+    }
+}
+
+/// Draws a room as text, with a character per cell and a line per row
+///
+/// Cells with tiles in any layer are drawn as the [`Collision::symbol`] of the strongest of them, so tiles that are
+/// only decoration are `.`, and cells without tiles are spaces. Objects are drawn over the tiles with
+/// [`object_symbol`]. With `color`, cells are coloured with ANSI escape codes for terminals.
+pub fn room_to_ascii(room: &Room, collisions: &CollisionTable, color: bool) -> String {
+    let layers = room.tile_grids();
+    let grid = collisions.room(room);
+    let objects: HashMap<(i32, i32), Category> = room
+        .object_tiles
+        .iter()
+        .map(|object| ((object.x, object.y), object.category))
+        .collect();
+
+    let mut text = String::new();
+    for y in 0..room.height as i32 {
+        // every line starts uncoloured
+        let mut current = "0";
+        for x in 0..room.width as i32 {
+            let (symbol, code) = if let Some(category) = objects.get(&(x, y)) {
+                (object_symbol(*category), object_color(*category))
+            } else if layers.iter().any(|layer| layer.get(x, y).is_some()) {
+                let collision = grid.get(x, y);
+                (collision.symbol(), collision_color(collision))
+            } else {
+                (' ', "0")
+            };
+            if color && current != code {
+                text.push_str(&format!("\x1b[{code}m"));
+                current = code;
+            }
+            text.push(symbol);
+        }
+        if color && current != "0" {
+            text.push_str("\x1b[0m");
+        }
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,234 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::level::Room;
+use crate::tile_grid::Grid;
+
+/// How the player collides with a tile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collision {
+    /// Nothing to collide with
+    #[default]
+    Empty,
+    Solid,
+    /// Can be jumped through from below and stood on from above
+    OneWay,
+    Slope,
+    /// Hurts the player, like spikes
+    Hazard,
+    /// Solid until it's broken
+    Breakable,
+}
+
+impl Collision {
+    /// Every kind of collision, from the weakest to the strongest
+    pub const ALL: [Collision; 6] = [
+        Collision::Empty,
+        Collision::OneWay,
+        Collision::Slope,
+        Collision::Hazard,
+        Collision::Breakable,
+        Collision::Solid,
+    ];
+
+    /// Character the collision is written as in a collision grid
+    pub fn symbol(&self) -> char {
+        match self {
+            Collision::Empty => '.',
+            Collision::Solid => '#',
+            Collision::OneWay => '=',
+            Collision::Slope => '/',
+            Collision::Hazard => '^',
+            Collision::Breakable => '%',
+        }
+    }
+
+    fn strength(&self) -> usize {
+        Collision::ALL
+            .iter()
+            .position(|other| other == self)
+            .unwrap()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CollisionEntry {
+    /// File name of the tileset's atlas
+    tileset: String,
+    /// First tile the entry covers
+    tile_id: i32,
+    /// How many tiles the entry covers, starting at `tile_id`
+    #[serde(default = "default_count")]
+    count: i32,
+    collision: Collision,
+}
+
+fn default_count() -> i32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct CollisionFile {
+    #[serde(rename = "tile", default)]
+    tiles: Vec<CollisionEntry>,
+}
+
+/// How every tile collides, keyed by tileset and tile ID
+///
+/// The level files and texture database don't say which tiles are solid, so it's read from a table instead. Tiles that
+/// aren't in the table go by which layer they're in, see [`layer_collision`].
+#[derive(Clone, Debug, Default)]
+pub struct CollisionTable {
+    /// Keyed by tileset, then tile ID
+    tiles: HashMap<String, HashMap<i32, Collision>>,
+}
+
+impl CollisionTable {
+    /// Loads the table from a TOML file with a `[[tile]]` entry per run of tiles
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<CollisionTable> {
+        let file: CollisionFile = toml::from_str(&fs::read_to_string(path)?)?;
+
+        let mut table = CollisionTable::default();
+        for entry in file.tiles {
+            if entry.count <= 0 {
+                bail!(
+                    "collision of tile {} in {} needs to cover at least one tile",
+                    entry.tile_id,
+                    entry.tileset
+                );
+            }
+            let tileset = table.tiles.entry(entry.tileset).or_default();
+            for tile_id in entry.tile_id..entry.tile_id + entry.count {
+                tileset.insert(tile_id, entry.collision);
+            }
+        }
+        Ok(table)
+    }
+
+    /// How a single tile collides, or `None` if the table doesn't say
+    pub fn tile(&self, tileset: &str, tile_id: i32) -> Option<Collision> {
+        self.tiles
+            .get(tileset)
+            .and_then(|tiles| tiles.get(&tile_id))
+            .copied()
+    }
+
+    /// How every cell of a room collides
+    ///
+    /// Every tile layer counts, so a cell with more than one tile takes the strongest of their collisions. Tiles that
+    /// aren't in the table go by [`layer_collision`].
+    pub fn room(&self, room: &Room) -> CollisionGrid {
+        let mut grid = CollisionGrid::new(room.width, room.height);
+        for (layer, tiles) in room.tile_grids().iter().enumerate() {
+            for tile in tiles.iter() {
+                let collision = self
+                    .tile(&room.tileset, tile.id)
+                    .unwrap_or_else(|| layer_collision(layer, tile.id));
+                if let Some(cell) = grid.get_mut(tile.x, tile.y)
+                    && collision.strength() > cell.strength()
+                {
+                    *cell = collision;
+                }
+            }
+        }
+        grid
+    }
+}
+
+/// How a tile that isn't in the collision table collides, by the index of its layer in [`Room::tile_grids`]
+///
+/// The middle layer holds the tiles the player walks on, with the bottom layer behind it and the top layer in front of
+/// it, so middle tiles are taken to be solid and the others empty. IDs from 512 up have special behaviour the level
+/// files don't describe, so they're empty too.
+pub fn layer_collision(layer: usize, tile_id: i32) -> Collision {
+    if layer == 1 && tile_id < 512 {
+        Collision::Solid
+    } else {
+        Collision::Empty
+    }
+}
+
+/// The collision of every cell in a room
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollisionGrid {
+    cells: Grid<Collision>,
+}
+
+impl CollisionGrid {
+    pub fn new(width: u32, height: u32) -> CollisionGrid {
+        CollisionGrid {
+            cells: Grid::new(width, height, Collision::Empty),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.cells.height()
+    }
+
+    /// Collision of a cell, or empty outside of the room
+    pub fn get(&self, x: i32, y: i32) -> Collision {
+        self.cells.get(x, y).copied().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut Collision> {
+        self.cells.get_mut(x, y)
+    }
+
+    /// Every row as a string of [`Collision::symbol`]s
+    pub fn rows(&self) -> Vec<String> {
+        if self.width() == 0 {
+            return Vec::new();
+        }
+        self.cells
+            .rows()
+            .map(|row| row.iter().map(Collision::symbol).collect())
+            .collect()
+    }
+}
+
+impl fmt::Display for CollisionGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            writeln!(f, "{row}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A room's collision grid as it's exported
+#[derive(Clone, Debug, Serialize)]
+pub struct RoomCollision {
+    pub level_id: i32,
+    pub room_id: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Which collision each symbol in `rows` stands for
+    pub legend: BTreeMap<char, Collision>,
+    /// Every row of the room from the top, as a string of symbols
+    pub rows: Vec<String>,
+}
+
+impl RoomCollision {
+    pub fn new(level_id: i32, room_id: i32, grid: &CollisionGrid) -> RoomCollision {
+        RoomCollision {
+            level_id,
+            room_id,
+            width: grid.width(),
+            height: grid.height(),
+            legend: Collision::ALL
+                .iter()
+                .map(|collision| (collision.symbol(), *collision))
+                .collect(),
+            rows: grid.rows(),
+        }
+    }
+}
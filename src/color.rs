@@ -0,0 +1,67 @@
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// A colour with 8 bits per channel, which isn't premultiplied by alpha
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgba(0, 0, 0, 255);
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// The channels in the order [`Image`](crate::render::Image) stores them
+    pub fn to_array(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+/// Parses a colour written the way the level files do, such as `{R:0 G:0 B:0 A:255}`
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Color> {
+        let re = Regex::new(r"^\{R:(\d+) G:(\d+) B:(\d+) A:(\d+)\}$").unwrap();
+        let captures = re
+            .captures(s)
+            .ok_or_else(|| anyhow!("{s:?} isn't a colour in the form {{R:0 G:0 B:0 A:255}}"))?;
+        let channel = |i: usize, name: &str| {
+            captures[i].parse::<u8>().with_context(|| {
+                format!(
+                    "{name} of colour {s:?} is {}, which isn't from 0 to 255",
+                    &captures[i]
+                )
+            })
+        };
+        Ok(Color::rgba(
+            channel(1, "R")?,
+            channel(2, "G")?,
+            channel(3, "B")?,
+            channel(4, "A")?,
+        ))
+    }
+}
+
+/// Writes a colour the way the level files do, so it parses back into the same colour
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{R:{} G:{} B:{} A:{}}}", self.r, self.g, self.b, self.a)
+    }
+}
+
+#[cfg(feature = "viewer")]
+impl From<Color> for sdl2::pixels::Color {
+    fn from(color: Color) -> sdl2::pixels::Color {
+        sdl2::pixels::Color::RGBA(color.r, color.g, color.b, color.a)
+    }
+}
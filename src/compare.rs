@@ -0,0 +1,116 @@
+use crate::era::Era;
+use crate::geometry::Point;
+use crate::level::Room;
+use crate::tile_grid::TileGrid;
+use crate::world::{PlacedRoom, World};
+
+/// How similar two rooms have to be before they're considered the same room in different eras
+pub const MIN_SIMILARITY: f32 = 0.5;
+
+/// A room matched up with its counterpart in another era
+#[derive(Clone, Copy, Debug)]
+pub struct RoomPair<'a> {
+    pub a: PlacedRoom<'a>,
+    pub b: PlacedRoom<'a>,
+    /// Fraction of non-empty cells in the bottom and middle layers that are the same in both rooms
+    pub similarity: f32,
+}
+
+/// How similar the bottom and middle layers of two rooms are, from 0 to 1, given their [`Room::tile_grids`]
+///
+/// Rooms of different sizes are never similar. Top tiles are left out as they're mostly decoration, which is what
+/// tends to change between eras.
+pub fn similarity(a: &[TileGrid; 3], b: &[TileGrid; 3]) -> f32 {
+    if a[0].width() != b[0].width() || a[0].height() != b[0].height() {
+        return 0.0;
+    }
+
+    let (mut same, mut total) = (0, 0);
+    for (a_grid, b_grid) in [(&a[0], &b[0]), (&a[1], &b[1])] {
+        for (a_cell, b_cell) in a_grid.cells().zip(b_grid.cells()) {
+            if a_cell.is_none() && b_cell.is_none() {
+                continue;
+            }
+            total += 1;
+            if a_cell == b_cell {
+                same += 1;
+            }
+        }
+    }
+
+    if total == 0 {
+        0.0
+    } else {
+        same as f32 / total as f32
+    }
+}
+
+/// Matches up rooms in era `a` with their counterparts in era `b`
+///
+/// Every pair of rooms is scored with [`similarity`], with ties going to rooms that share an ID, and the best scoring
+/// pairs are picked first so every room ends up in at most one pair. Pairs below [`MIN_SIMILARITY`] are left out.
+pub fn pair_rooms(world: &World, a: Era, b: Era) -> Vec<RoomPair<'_>> {
+    let a_rooms: Vec<PlacedRoom> = world.rooms().filter(|room| room.era == a).collect();
+    let b_rooms: Vec<PlacedRoom> = world.rooms().filter(|room| room.era == b).collect();
+    let a_grids: Vec<[TileGrid; 3]> = a_rooms.iter().map(|room| room.room.tile_grids()).collect();
+    let b_grids: Vec<[TileGrid; 3]> = b_rooms.iter().map(|room| room.room.tile_grids()).collect();
+
+    let mut candidates = Vec::new();
+    for (i, a_room) in a_rooms.iter().enumerate() {
+        for (j, b_room) in b_rooms.iter().enumerate() {
+            let similarity = similarity(&a_grids[i], &b_grids[j]);
+            if similarity >= MIN_SIMILARITY {
+                let same_id = a_room.room.id == b_room.room.id;
+                candidates.push((similarity, same_id, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.0.total_cmp(&x.0).then(y.1.cmp(&x.1)));
+
+    let mut a_used = vec![false; a_rooms.len()];
+    let mut b_used = vec![false; b_rooms.len()];
+    let mut pairs = Vec::new();
+    for (similarity, _, i, j) in candidates {
+        if a_used[i] || b_used[j] {
+            continue;
+        }
+        a_used[i] = true;
+        b_used[j] = true;
+        pairs.push(RoomPair {
+            a: a_rooms[i],
+            b: b_rooms[j],
+            similarity,
+        });
+    }
+    pairs
+}
+
+/// Every cell where any layer differs between two rooms, in tiles
+///
+/// Cells outside of the smaller room are compared against empty cells.
+pub fn diff_tiles(a: &Room, b: &Room) -> Vec<Point> {
+    let width = a.width.max(b.width);
+    let height = a.height.max(b.height);
+
+    let mut cells = vec![false; (width * height) as usize];
+    for (a_tiles, b_tiles) in [
+        (&a.bottom_tiles, &b.bottom_tiles),
+        (&a.middle_tiles, &b.middle_tiles),
+        (&a.top_tiles, &b.top_tiles),
+    ] {
+        let a_grid = TileGrid::from_tiles(width, height, a_tiles);
+        let b_grid = TileGrid::from_tiles(width, height, b_tiles);
+        for (i, (a_cell, b_cell)) in a_grid.cells().zip(b_grid.cells()).enumerate() {
+            if a_cell != b_cell {
+                cells[i] = true;
+            }
+        }
+    }
+
+    cells
+        .into_iter()
+        .enumerate()
+        .filter(|(_, differs)| *differs)
+        .map(|(i, _)| Point::new((i as u32 % width) as i32, (i as u32 / width) as i32))
+        .collect()
+}
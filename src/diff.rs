@@ -0,0 +1,435 @@
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::TILE_SIZE;
+use crate::geometry::{Point, Rect};
+use crate::level::{Category, Level, ObjectTile, Room, Tile};
+use crate::render::{Image, Renderer};
+use crate::tile_grid::TileGrid;
+use crate::world::World;
+
+/// Space between rooms in a rendered diff, in pixels
+const RENDER_GAP: u32 = TILE_SIZE as u32 * 2;
+const HIGHLIGHT: [u8; 4] = [255, 0, 0, 96];
+
+/// Changes to a single tile layer, keyed by cell
+#[derive(Clone, Debug, Default)]
+pub struct LayerDiff {
+    pub added: Vec<Tile>,
+    pub removed: Vec<Tile>,
+    /// Cells where the tile was replaced, as (old, new)
+    pub changed: Vec<(Tile, Tile)>,
+}
+
+impl LayerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Every cell that changed, in tiles
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .chain(self.changed.iter().map(|(_, new)| new))
+            .map(|tile| (tile.x, tile.y))
+    }
+}
+
+/// Changes to a room that's in both versions of a level
+#[derive(Clone, Debug)]
+pub struct RoomDiff {
+    pub id: i32,
+    pub name: String,
+    /// Properties that changed, as (property, old, new)
+    pub properties: Vec<(&'static str, String, String)>,
+    pub bottom_tiles: LayerDiff,
+    pub middle_tiles: LayerDiff,
+    pub top_tiles: LayerDiff,
+    pub added_objects: Vec<ObjectTile>,
+    pub removed_objects: Vec<ObjectTile>,
+}
+
+impl RoomDiff {
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+            && self.layers().iter().all(|(_, layer)| layer.is_empty())
+            && self.added_objects.is_empty()
+            && self.removed_objects.is_empty()
+    }
+
+    /// The tile layers along with their names
+    pub fn layers(&self) -> [(&'static str, &LayerDiff); 3] {
+        [
+            ("bottom tiles", &self.bottom_tiles),
+            ("middle tiles", &self.middle_tiles),
+            ("top tiles", &self.top_tiles),
+        ]
+    }
+
+    /// Every cell where a tile or object changed, in tiles
+    pub fn changed_cells(&self) -> BTreeSet<(i32, i32)> {
+        self.layers()
+            .into_iter()
+            .flat_map(|(_, layer)| layer.cells())
+            .chain(
+                self.added_objects
+                    .iter()
+                    .chain(&self.removed_objects)
+                    .map(|object| (object.x, object.y)),
+            )
+            .collect()
+    }
+}
+
+/// Changes between two versions of a level
+#[derive(Clone, Debug)]
+pub struct LevelDiff {
+    pub id: i32,
+    pub name: String,
+    pub added_rooms: Vec<i32>,
+    pub removed_rooms: Vec<i32>,
+    pub changed_rooms: Vec<RoomDiff>,
+}
+
+impl LevelDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_rooms.is_empty()
+            && self.removed_rooms.is_empty()
+            && self.changed_rooms.is_empty()
+    }
+}
+
+/// Changes between two installations, going by minimap area
+#[derive(Clone, Debug, Default)]
+pub struct WorldDiff {
+    pub added_levels: Vec<i32>,
+    pub removed_levels: Vec<i32>,
+    /// Levels that changed, along with the ID of their area
+    pub changed_levels: Vec<(i32, LevelDiff)>,
+    /// Rooms the minimap moved, as (area ID, room ID, old position, new position)
+    pub moved_rooms: Vec<(i32, i32, Point, Point)>,
+}
+
+impl WorldDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_levels.is_empty()
+            && self.removed_levels.is_empty()
+            && self.changed_levels.is_empty()
+            && self.moved_rooms.is_empty()
+    }
+}
+
+/// Compares a layer cell by cell, over every cell that's in either version of the room
+///
+/// Tiles outside of the room aren't in its grid, so they aren't compared, but `validate` reports them.
+fn diff_layer(old: &TileGrid, new: &TileGrid) -> LayerDiff {
+    let mut diff = LayerDiff::default();
+    for y in 0..old.height().max(new.height()) as i32 {
+        for x in 0..old.width().max(new.width()) as i32 {
+            match (old.get(x, y), new.get(x, y)) {
+                (Some(old_tile), None) => diff.removed.push(old_tile),
+                (None, Some(new_tile)) => diff.added.push(new_tile),
+                (Some(old_tile), Some(new_tile)) if old_tile != new_tile => {
+                    diff.changed.push((old_tile, new_tile))
+                }
+                _ => (),
+            }
+        }
+    }
+    diff
+}
+
+pub fn diff_rooms(old: &Room, new: &Room) -> RoomDiff {
+    let mut properties = Vec::new();
+    for (property, old_value, new_value) in [
+        ("name", old.name.clone(), new.name.clone()),
+        ("index", old.index.to_string(), new.index.to_string()),
+        ("tileset", old.tileset.clone(), new.tileset.clone()),
+        ("width", old.width.to_string(), new.width.to_string()),
+        ("height", old.height.to_string(), new.height.to_string()),
+        (
+            "background wipe color",
+            old.background_wipe_color.to_string(),
+            new.background_wipe_color.to_string(),
+        ),
+    ] {
+        if old_value != new_value {
+            properties.push((property, old_value, new_value));
+        }
+    }
+
+    // objects can share a cell, so they're matched up one by one instead
+    let mut added_objects = new.object_tiles.clone();
+    let mut removed_objects = Vec::new();
+    for object in &old.object_tiles {
+        match added_objects.iter().position(|other| other == object) {
+            Some(i) => {
+                added_objects.remove(i);
+            }
+            None => removed_objects.push(object.clone()),
+        }
+    }
+
+    let [old_bottom, old_middle, old_top] = old.tile_grids();
+    let [new_bottom, new_middle, new_top] = new.tile_grids();
+    RoomDiff {
+        id: new.id,
+        name: new.name.clone(),
+        properties,
+        bottom_tiles: diff_layer(&old_bottom, &new_bottom),
+        middle_tiles: diff_layer(&old_middle, &new_middle),
+        top_tiles: diff_layer(&old_top, &new_top),
+        added_objects,
+        removed_objects,
+    }
+}
+
+/// Compares two versions of a level, matching rooms up by ID
+pub fn diff_levels(old: &Level, new: &Level) -> LevelDiff {
+    let mut diff = LevelDiff {
+        id: new.id,
+        name: new.name.clone(),
+        added_rooms: Vec::new(),
+        removed_rooms: Vec::new(),
+        changed_rooms: Vec::new(),
+    };
+
+    for old_room in &old.rooms {
+        match new.rooms.iter().find(|room| room.id == old_room.id) {
+            Some(new_room) => {
+                let room_diff = diff_rooms(old_room, new_room);
+                if !room_diff.is_empty() {
+                    diff.changed_rooms.push(room_diff);
+                }
+            }
+            None => diff.removed_rooms.push(old_room.id),
+        }
+    }
+    for new_room in &new.rooms {
+        if !old.rooms.iter().any(|room| room.id == new_room.id) {
+            diff.added_rooms.push(new_room.id);
+        }
+    }
+
+    diff
+}
+
+/// Compares two installations, matching levels up by the minimap area they belong to
+pub fn diff_worlds(old: &World, new: &World) -> WorldDiff {
+    let mut diff = WorldDiff::default();
+
+    let area_ids: BTreeSet<i32> = old
+        .levels
+        .keys()
+        .chain(new.levels.keys())
+        .copied()
+        .collect();
+    for id in area_ids {
+        match (old.levels.get(&id), new.levels.get(&id)) {
+            (Some(old_level), Some(new_level)) => {
+                let level_diff = diff_levels(old_level, new_level);
+                if !level_diff.is_empty() {
+                    diff.changed_levels.push((id, level_diff));
+                }
+            }
+            (None, Some(_)) => diff.added_levels.push(id),
+            (Some(_), None) => diff.removed_levels.push(id),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    for new_room in new.rooms() {
+        if let Some(old_room) = old.room(new_room.area.id, new_room.room.id) {
+            let (old_position, new_position) =
+                (old_room.placement.position, new_room.placement.position);
+            if old_position != new_position {
+                diff.moved_rooms.push((
+                    new_room.area.id,
+                    new_room.room.id,
+                    old_position,
+                    new_position,
+                ));
+            }
+        }
+    }
+
+    diff
+}
+
+/// Draws the old and new version of changed rooms next to each other, one pair per row, with changed cells highlighted
+///
+/// Each entry is the old room, the new room, and the diff between them.
+pub fn render_changes(
+    changes: &[(&Room, &Room, &RoomDiff)],
+    renderer: &mut Renderer,
+) -> Result<Image> {
+    let column_width = changes
+        .iter()
+        .map(|(old, new, _)| old.width.max(new.width) * TILE_SIZE as u32)
+        .max()
+        .unwrap_or(0);
+    let row_heights: Vec<u32> = changes
+        .iter()
+        .map(|(old, new, _)| old.height.max(new.height) * TILE_SIZE as u32)
+        .collect();
+
+    let mut image = Image::new(
+        column_width * 2 + RENDER_GAP,
+        row_heights
+            .iter()
+            .map(|height| height + RENDER_GAP)
+            .sum::<u32>(),
+    );
+
+    let mut y = 0;
+    for ((old, new, diff), height) in changes.iter().zip(row_heights) {
+        for (room, x) in [(old, 0), (new, column_width + RENDER_GAP)] {
+            renderer.draw_room(&mut image, room, x as i32, y as i32)?;
+            for (cell_x, cell_y) in diff.changed_cells() {
+                image.fill_rect(
+                    Rect::new(
+                        x as i32 + cell_x * TILE_SIZE,
+                        y as i32 + cell_y * TILE_SIZE,
+                        TILE_SIZE as u32,
+                        TILE_SIZE as u32,
+                    ),
+                    HIGHLIGHT,
+                );
+            }
+        }
+        y += height + RENDER_GAP;
+    }
+
+    Ok(image)
+}
+
+fn describe_tile(tile: &Tile) -> String {
+    let mut description = format!("tile {}", tile.id);
+    if tile.flip_x {
+        description.push_str(" flipped x");
+    }
+    if tile.flip_y {
+        description.push_str(" flipped y");
+    }
+    description
+}
+
+fn describe_object(object: &ObjectTile) -> String {
+    let mut description = match object.category {
+        Category::None => format!("({}, {}) object {}", object.x, object.y, object.id),
+        Category::Event(event) => format!("({}, {}) event {event:?}", object.x, object.y),
+        Category::Enemy(enemy) => format!("({}, {}) enemy {enemy:?}", object.x, object.y),
+        Category::Item(item) => format!("({}, {}) item {item:?}", object.x, object.y),
+    };
+    if let Some(argument) = object.argument {
+        description.push_str(&format!(" argument {argument}"));
+    }
+    description
+}
+
+impl fmt::Display for RoomDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  room {} ({})", self.id, self.name)?;
+        for (property, old, new) in &self.properties {
+            writeln!(f, "    {property}: {old:?} -> {new:?}")?;
+        }
+        for (name, layer) in self.layers() {
+            if layer.is_empty() {
+                continue;
+            }
+            writeln!(
+                f,
+                "    {name}: {} added, {} removed, {} changed",
+                layer.added.len(),
+                layer.removed.len(),
+                layer.changed.len()
+            )?;
+            for tile in &layer.added {
+                writeln!(
+                    f,
+                    "      + ({}, {}) {}",
+                    tile.x,
+                    tile.y,
+                    describe_tile(tile)
+                )?;
+            }
+            for tile in &layer.removed {
+                writeln!(
+                    f,
+                    "      - ({}, {}) {}",
+                    tile.x,
+                    tile.y,
+                    describe_tile(tile)
+                )?;
+            }
+            for (old, new) in &layer.changed {
+                writeln!(
+                    f,
+                    "      ~ ({}, {}) {} -> {}",
+                    new.x,
+                    new.y,
+                    describe_tile(old),
+                    describe_tile(new)
+                )?;
+            }
+        }
+        if !self.added_objects.is_empty() || !self.removed_objects.is_empty() {
+            writeln!(
+                f,
+                "    objects: {} added, {} removed",
+                self.added_objects.len(),
+                self.removed_objects.len()
+            )?;
+            for object in &self.added_objects {
+                writeln!(f, "      + {}", describe_object(object))?;
+            }
+            for object in &self.removed_objects {
+                writeln!(f, "      - {}", describe_object(object))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for LevelDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "level {} ({})", self.id, self.name)?;
+        for id in &self.added_rooms {
+            writeln!(f, "  added room {id}")?;
+        }
+        for id in &self.removed_rooms {
+            writeln!(f, "  removed room {id}")?;
+        }
+        for room in &self.changed_rooms {
+            write!(f, "{room}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for id in &self.added_levels {
+            writeln!(f, "added level for area {id}")?;
+        }
+        for id in &self.removed_levels {
+            writeln!(f, "removed level for area {id}")?;
+        }
+        for (area, room, old, new) in &self.moved_rooms {
+            writeln!(
+                f,
+                "moved room {area}:{room} from ({}, {}) to ({}, {})",
+                old.x, old.y, new.x, new.y
+            )?;
+        }
+        for (_, level) in &self.changed_levels {
+            write!(f, "{level}")?;
+        }
+        Ok(())
+    }
+}
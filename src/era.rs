@@ -0,0 +1,207 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::minimap::Minimap;
+
+/// Which of the game's time periods an area belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Era {
+    Present,
+    Past,
+    /// The era below the past on the minimap, which the game doesn't name
+    Unknown,
+}
+
+impl Era {
+    /// Every era, from the top of the minimap to the bottom
+    pub const ALL: [Era; 3] = [Era::Present, Era::Past, Era::Unknown];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Era::Present => "Present",
+            Era::Past => "Past",
+            Era::Unknown => "???",
+        }
+    }
+}
+
+impl fmt::Display for Era {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EraEntry {
+    /// ID of the minimap area
+    id: i32,
+    era: Era,
+}
+
+#[derive(Debug, Deserialize)]
+struct EraFile {
+    #[serde(rename = "area", default)]
+    areas: Vec<EraEntry>,
+}
+
+/// The era of areas that are given explicitly instead of worked out from the minimap, keyed by area ID
+#[derive(Clone, Debug, Default)]
+pub struct EraTable {
+    areas: HashMap<i32, Era>,
+}
+
+impl EraTable {
+    /// Loads the table from a TOML file with an `[[area]]` entry per area
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<EraTable> {
+        let file: EraFile = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut table = EraTable::default();
+        for entry in file.areas {
+            table.insert(entry.id, entry.era);
+        }
+        Ok(table)
+    }
+
+    pub fn insert(&mut self, area_id: i32, era: Era) {
+        self.areas.insert(area_id, era);
+    }
+
+    pub fn get(&self, area_id: i32) -> Option<Era> {
+        self.areas.get(&area_id).copied()
+    }
+}
+
+/// Works out the era of every area on the minimap, keyed by area ID
+///
+/// Areas in `table` get the era it gives them. Eras aren't stored anywhere in the game files, so the rest fall back to
+/// a guess from the layout: the minimap stacks the eras on top of each other, so the areas are sorted by how far down
+/// they start and split wherever there are the biggest empty gaps between them. Areas in the table are left out of the
+/// guess, so one placed somewhere odd doesn't throw the split off. Areas without any rooms are left out.
+pub fn classify_eras(minimap: &Minimap, table: &EraTable) -> HashMap<i32, Era> {
+    let mut eras = HashMap::new();
+    let mut extents: Vec<(i32, i32, i32)> = Vec::new();
+    for area in &minimap.area_list.areas {
+        let rooms = &area.room_list.rooms;
+        let Some(top) = rooms.iter().map(|room| room.position.y).min() else {
+            continue;
+        };
+        if let Some(era) = table.get(area.id) {
+            eras.insert(area.id, era);
+            continue;
+        }
+        let bottom = rooms
+            .iter()
+            .map(|room| room.position.y + room.height as i32)
+            .max()
+            .unwrap_or(top);
+        extents.push((top, bottom, area.id));
+    }
+    extents.sort();
+
+    // (size of the gap, index of the first area after it)
+    let mut gaps = Vec::new();
+    let mut reached = i32::MIN;
+    for (i, &(top, bottom, _)) in extents.iter().enumerate() {
+        if i > 0 && top > reached {
+            gaps.push((top - reached, i));
+        }
+        reached = reached.max(bottom);
+    }
+    gaps.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut boundaries: Vec<usize> = gaps
+        .into_iter()
+        .take(Era::ALL.len() - 1)
+        .map(|(_, i)| i)
+        .collect();
+    boundaries.sort();
+
+    let mut era = 0;
+    for (i, &(_, _, id)) in extents.iter().enumerate() {
+        if boundaries.get(era) == Some(&i) {
+            era += 1;
+        }
+        eras.insert(id, Era::ALL[era]);
+    }
+    eras
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+    use crate::minimap::{Area, AreaList, Room, RoomList};
+
+    /// An area with a single room of one cell at `(x, y)`
+    fn area(id: i32, x: i32, y: i32) -> Area {
+        Area {
+            id,
+            room_list: RoomList {
+                rooms: vec![Room {
+                    id: 0,
+                    width: 1,
+                    height: 1,
+                    position: Point::new(x, y),
+                }],
+            },
+        }
+    }
+
+    fn minimap(areas: Vec<Area>) -> Minimap {
+        Minimap {
+            area_list: AreaList { areas },
+        }
+    }
+
+    #[test]
+    fn splits_at_the_biggest_gaps() {
+        let minimap = minimap(vec![
+            area(1, 0, 0),
+            area(2, 5, 1),
+            area(3, 0, 10),
+            area(4, 3, 12),
+            area(5, 0, 30),
+        ]);
+        let eras = classify_eras(&minimap, &EraTable::default());
+        assert_eq!(eras[&1], Era::Present);
+        assert_eq!(eras[&2], Era::Present);
+        assert_eq!(eras[&3], Era::Past);
+        assert_eq!(eras[&4], Era::Past);
+        assert_eq!(eras[&5], Era::Unknown);
+    }
+
+    #[test]
+    fn table_overrides_the_layout() {
+        // area 6 sits on its own far below everything, which would take one of the splits if it was guessed
+        let minimap = minimap(vec![
+            area(1, 0, 0),
+            area(2, 0, 10),
+            area(3, 0, 20),
+            area(6, 0, 100),
+        ]);
+        let mut table = EraTable::default();
+        table.insert(6, Era::Present);
+        let eras = classify_eras(&minimap, &table);
+        assert_eq!(eras[&1], Era::Present);
+        assert_eq!(eras[&2], Era::Past);
+        assert_eq!(eras[&3], Era::Unknown);
+        assert_eq!(eras[&6], Era::Present);
+    }
+
+    #[test]
+    fn loads_table_from_toml() {
+        let file: EraFile = toml::from_str(
+            r#"
+            [[area]]
+            id = 17
+            era = "unknown"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.areas[0].id, 17);
+        assert_eq!(file.areas[0].era, Era::Unknown);
+    }
+}
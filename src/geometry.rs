@@ -0,0 +1,179 @@
+use std::ops::{Add, Sub};
+
+/// A position in pixels or tiles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// An area in pixels or tiles, from its top left corner
+///
+/// Unlike SDL's rectangles, these can be empty, which they are when either side is 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn left(&self) -> i32 {
+        self.x
+    }
+
+    pub fn top(&self) -> i32 {
+        self.y
+    }
+
+    /// The x coordinate just past the right edge
+    pub fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    /// The y coordinate just past the bottom edge
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    pub fn top_left(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(
+            self.x + self.width as i32 / 2,
+            self.y + self.height as i32 / 2,
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Moves the rectangle by an amount in each direction
+    pub fn offset(&mut self, x: i32, y: i32) {
+        self.x += x;
+        self.y += y;
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        point.x >= self.left()
+            && point.x < self.right()
+            && point.y >= self.top()
+            && point.y < self.bottom()
+    }
+
+    pub fn has_intersection(&self, other: Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The area both rectangles cover, or `None` if they don't overlap
+    pub fn intersection(&self, other: Rect) -> Option<Rect> {
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (right > left && bottom > top)
+            .then(|| Rect::new(left, top, (right - left) as u32, (bottom - top) as u32))
+    }
+
+    /// The smallest rectangle covering both, ignoring empty rectangles
+    pub fn union(&self, other: Rect) -> Rect {
+        if self.is_empty() {
+            return other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let left = self.left().min(other.left());
+        let top = self.top().min(other.top());
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect::new(left, top, (right - left) as u32, (bottom - top) as u32)
+    }
+}
+
+#[cfg(feature = "viewer")]
+impl From<Point> for sdl2::rect::Point {
+    fn from(point: Point) -> sdl2::rect::Point {
+        sdl2::rect::Point::new(point.x, point.y)
+    }
+}
+
+#[cfg(feature = "viewer")]
+impl From<sdl2::rect::Point> for Point {
+    fn from(point: sdl2::rect::Point) -> Point {
+        Point::new(point.x(), point.y())
+    }
+}
+
+#[cfg(feature = "viewer")]
+impl From<Rect> for sdl2::rect::Rect {
+    fn from(rect: Rect) -> sdl2::rect::Rect {
+        sdl2::rect::Rect::new(rect.x, rect.y, rect.width, rect.height)
+    }
+}
+
+#[cfg(feature = "viewer")]
+impl From<sdl2::rect::Rect> for Rect {
+    fn from(rect: sdl2::rect::Rect) -> Rect {
+        Rect::new(rect.x(), rect.y(), rect.width(), rect.height())
+    }
+}
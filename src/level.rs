@@ -1,7 +1,9 @@
 use num_enum::TryFromPrimitive;
 use serde::Deserialize;
 
+use crate::color::Color;
 use crate::parse_bool;
+use crate::tile_grid::TileGrid;
 
 #[derive(Clone, Debug, Default)]
 pub struct Level {
@@ -18,7 +20,8 @@ pub struct Room {
     pub tileset: String,
     pub width: u32,
     pub height: u32,
-    pub background_wipe_color: String,
+    /// Colour the room is filled with before its tiles are drawn
+    pub background_wipe_color: Color,
     pub bottom_tiles: Vec<Tile>,
     pub middle_tiles: Vec<Tile>,
     pub top_tiles: Vec<Tile>,
@@ -27,7 +30,7 @@ pub struct Room {
     // todo: backgrounds
 }
 
-#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
 pub struct Tile {
     #[serde(rename = "@ID")]
     pub id: i32,
@@ -42,6 +45,21 @@ pub struct Tile {
     pub flip_y: bool,
 }
 
+impl Room {
+    /// The bottom, middle and top layers as grids the size of the room
+    pub fn tile_grids(&self) -> [TileGrid; 3] {
+        [&self.bottom_tiles, &self.middle_tiles, &self.top_tiles]
+            .map(|tiles| TileGrid::from_tiles(self.width, self.height, tiles))
+    }
+
+    /// Replaces the bottom, middle and top layers with the tiles in grids
+    pub fn set_tile_grids(&mut self, [bottom, middle, top]: [TileGrid; 3]) {
+        self.bottom_tiles = bottom.to_tiles();
+        self.middle_tiles = middle.to_tiles();
+        self.top_tiles = top.to_tiles();
+    }
+}
+
 impl From<ObjectTile> for Tile {
     fn from(value: ObjectTile) -> Self {
         Tile {
@@ -54,7 +72,7 @@ impl From<ObjectTile> for Tile {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ObjectTile {
     pub id: i32,
     // skip layer, seems redundant
@@ -66,7 +84,7 @@ pub struct ObjectTile {
     pub argument: Option<i32>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Category {
     None,
     Event(Event),
@@ -74,7 +92,29 @@ pub enum Category {
     Item(Item),
 }
 
-#[derive(Clone, Copy, Debug, TryFromPrimitive)]
+impl Category {
+    /// Name of the category as written in the level files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::None => "None",
+            Category::Event(_) => "Event",
+            Category::Enemy(_) => "Enemy",
+            Category::Item(_) => "Item",
+        }
+    }
+
+    /// ID of the object within its category
+    pub fn object_id(&self) -> u8 {
+        match self {
+            Category::None => 0,
+            Category::Event(event) => *event as u8,
+            Category::Enemy(enemy) => *enemy as u8,
+            Category::Item(item) => *item as u8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Event {
     Checkpoint,
@@ -128,7 +168,7 @@ pub enum Event {
     EscortMissionManager,
 }
 
-#[derive(Clone, Copy, Debug, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Enemy {
     CheveuxTank,
@@ -195,7 +235,7 @@ pub enum Enemy {
     CantoranBoss,
 }
 
-#[derive(Clone, Copy, Debug, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Item {
     MaxHP,
@@ -5,8 +5,9 @@ use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
+use crate::color::Color;
 use crate::level::{Level, Tile};
-use crate::parse_bool;
+use crate::{parse_bool, parse_color};
 
 #[derive(Debug, Deserialize)]
 struct LevelSpecification {
@@ -40,8 +41,8 @@ struct Room {
     width: u32,
     #[serde(rename = "@Height")]
     height: u32,
-    #[serde(rename = "@BackgroundWipeColor")]
-    background_wipe_color: String,
+    #[serde(rename = "@BackgroundWipeColor", deserialize_with = "parse_color")]
+    background_wipe_color: Color,
     bottom_tiles: TileList,
     middle_tiles: TileList,
     top_tiles: TileList,
@@ -114,16 +115,7 @@ impl From<ObjectTile> for crate::level::ObjectTile {
             y: value.y,
             flip_x: value.flip_x,
             flip_y: value.flip_y,
-            category: match value.category {
-                Category::None => crate::level::Category::None,
-                Category::Event => {
-                    crate::level::Category::Event(value.object_id.try_into().unwrap())
-                }
-                Category::Enemy => {
-                    crate::level::Category::Enemy(value.object_id.try_into().unwrap())
-                }
-                Category::Item => crate::level::Category::Item(value.object_id.try_into().unwrap()),
-            },
+            category: value.category.with_object_id(value.object_id).unwrap(),
             argument: value.argument,
         }
     }
@@ -137,6 +129,17 @@ pub enum Category {
     Item,
 }
 
+impl Category {
+    pub(crate) fn with_object_id(self, object_id: u8) -> Result<crate::level::Category> {
+        Ok(match self {
+            Category::None => crate::level::Category::None,
+            Category::Event => crate::level::Category::Event(object_id.try_into()?),
+            Category::Enemy => crate::level::Category::Enemy(object_id.try_into()?),
+            Category::Item => crate::level::Category::Item(object_id.try_into()?),
+        })
+    }
+}
+
 pub fn load_map_from_file<T: AsRef<Path>>(path: T) -> Result<Level> {
     let file = File::open(path)?;
     let decoder = ZlibDecoder::new(file);
@@ -1,15 +1,35 @@
+use color::Color;
+use geometry::Point;
 use regex::Regex;
-use sdl2::rect::Point;
 use serde::Deserializer;
 use serde::de::Visitor;
 
+pub mod animation;
+pub mod ascii;
+pub mod collision;
+pub mod color;
+pub mod compare;
+pub mod diff;
+pub mod era;
+pub mod geometry;
 pub mod level;
 mod level_specification;
 pub mod minimap;
+pub mod render;
+pub mod svg;
 pub mod texture_atlas;
+pub mod thumbnail;
+pub mod tile_grid;
+pub mod tiled;
+pub mod validate;
+pub mod web_map;
+pub mod world;
 
 pub use level_specification::load_map_from_file;
 
+/// Tile size in pixels
+pub const TILE_SIZE: i32 = 16;
+
 struct BoolVisitor;
 
 impl<'de> Visitor<'de> for BoolVisitor {
@@ -78,3 +98,26 @@ impl Visitor<'_> for PointVisitor {
         }
     }
 }
+
+fn parse_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(ColorVisitor)
+}
+
+struct ColorVisitor;
+impl Visitor<'_> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("colour data in the form of a string, such as {R:0 G:0 B:0 A:255}")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.parse().map_err(|err| E::custom(format!("{err:#}")))
+    }
+}
@@ -1,348 +1,644 @@
-use clap::Parser;
-use sdl2::EventPump;
-use sdl2::event::Event;
-use sdl2::image::LoadTexture;
-use sdl2::pixels::Color;
-use sdl2::rect::{Point, Rect};
-use sdl2::render::{Canvas, Texture, TextureCreator};
-use sdl2::video::{Window, WindowContext};
-use std::collections::HashMap;
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::process::exit;
+use timespinner_map::collision::{CollisionTable, RoomCollision};
+use timespinner_map::color::Color;
+use timespinner_map::era::EraTable;
+use timespinner_map::level::Category;
+use timespinner_map::render::Renderer;
 use timespinner_map::texture_atlas::TextureDatabase;
-use timespinner_map::{
-    level::{Level, Tile},
-    load_map_from_file,
-    minimap::Minimap,
-};
+use timespinner_map::thumbnail::{self, Thumbnails};
+use timespinner_map::world::World;
+use timespinner_map::{ascii, diff, load_map_from_file, svg, tiled, validate, web_map};
+
+#[cfg(feature = "viewer")]
+mod viewer;
+
+/// File the era of areas is loaded from, if it exists
+const ERAS_PATH: &str = "eras.toml";
 
 #[derive(Debug, Parser)]
 struct Args {
     /// Path to your Timespinner installation directory
     timespinner_path: PathBuf,
+    /// What to do, opening the viewer if left out
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Open the map viewer
+    View {
+        /// Start centered on a room, given as AREA:ROOM
+        #[arg(long, value_parser = parse_room, conflicts_with = "at")]
+        room: Option<(i32, i32)>,
+        /// Start with the camera at a position in pixels, given as X,Y
+        #[arg(long, value_parser = parse_at)]
+        at: Option<(i32, i32)>,
+    },
+    /// Render every room into its own PNG
+    Render {
+        /// Directory to write the images into, one subdirectory per level
+        out_dir: PathBuf,
+        /// Only render the level with this area ID
+        #[arg(long, conflicts_with = "room")]
+        area: Option<i32>,
+        /// Only render this room, given as AREA:ROOM
+        #[arg(long, value_parser = parse_room)]
+        room: Option<(i32, i32)>,
+    },
+    /// Export the map in another format
+    #[command(subcommand)]
+    Export(Export),
+    /// List every room, one per line
+    ListRooms {
+        /// Print the rooms as JSON instead of one per line
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a summary of the levels and minimap, or with an area and room, the room's details and tiles as text
+    Info {
+        /// Area ID of the room to print
+        #[arg(requires = "room")]
+        area: Option<i32>,
+        /// ID of the room to print
+        room: Option<i32>,
+        /// Table of how each tile collides, which picks the character each tile is drawn as
+        #[arg(long, default_value = "collision.toml")]
+        table: PathBuf,
+        /// Don't colour the room, which is only coloured when printing to a terminal anyway
+        #[arg(long)]
+        no_color: bool,
+    },
+    /// Check the levels and minimap for problems, exiting with an error if there are any
+    Validate {
+        /// Print the problems as JSON instead of one per line
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare with another installation, or if both paths are level files, compare the two levels
+    Diff {
+        /// Newer installation directory or level file, compared against TIMESPINNER_PATH
+        other: PathBuf,
+        /// Also render the old and new version of every changed room into this PNG, with changes highlighted
+        #[arg(long)]
+        png: Option<PathBuf>,
+        /// Installation to load textures from for --png when comparing level files, by default the one OTHER is in
+        #[arg(long)]
+        install: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum Export {
+    /// Export levels as Tiled maps and tilesets
+    Tiled {
+        /// Directory to write the maps into, one subdirectory per level
+        out_dir: PathBuf,
+        /// Only export the level with this area ID
+        #[arg(long)]
+        area: Option<i32>,
+    },
+    /// Export the minimap layout as an SVG with a clickable rectangle per room
+    Svg {
+        /// File to write the SVG to
+        out: PathBuf,
+        /// Embed a thumbnail of every room
+        #[arg(long)]
+        thumbnails: bool,
+        /// Directory thumbnails are cached in
+        #[arg(long, default_value = thumbnail::CACHE_DIR)]
+        thumbnail_cache: PathBuf,
+    },
+    /// Render the whole map as a tile pyramid with a web page to browse it
+    WebMap {
+        /// Directory to write the tiles and index.html into
+        out_dir: PathBuf,
+    },
+    /// Export how every cell of every room collides, as a JSON file per room
+    Collision {
+        /// Directory to write the grids into, one subdirectory per level
+        out_dir: PathBuf,
+        /// Table of how each tile collides
+        #[arg(long, default_value = "collision.toml")]
+        table: PathBuf,
+        /// Only export the level with this area ID
+        #[arg(long)]
+        area: Option<i32>,
+    },
 }
 
-/// Tile size in pixels
-const TILE_SIZE: i32 = 16;
-/// Room width in tiles
-const ROOM_WIDTH: i32 = 25;
-/// Room height in tiles
-const ROOM_HEIGHT: i32 = 20;
+#[cfg(feature = "viewer")]
+fn view(path: &Path, room: Option<(i32, i32)>, at: Option<(i32, i32)>) -> anyhow::Result<()> {
+    use viewer::StartAt;
+
+    let start_at = match (room, at) {
+        (Some((area_id, room_id)), _) => Some(StartAt::Room(area_id, room_id)),
+        (None, Some((x, y))) => Some(StartAt::Point(x, y)),
+        (None, None) => None,
+    };
+    // the viewer is the only command that needs SDL to be initialized
+    viewer::run(path, start_at)
+}
 
-fn main() {
+#[cfg(not(feature = "viewer"))]
+fn view(_path: &Path, _room: Option<(i32, i32)>, _at: Option<(i32, i32)>) -> anyhow::Result<()> {
+    anyhow::bail!("the viewer isn't available, build with the \"viewer\" feature to use it")
+}
+
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let mut state = State::new(args.timespinner_path);
+    let path = &args.timespinner_path;
+
+    let command = args.command.unwrap_or(Command::View {
+        room: None,
+        at: None,
+    });
+    match command {
+        Command::View { room, at } => view(path, room, at)?,
+        Command::Render {
+            out_dir,
+            area,
+            room,
+        } => render_rooms(path, &out_dir, area, room)?,
+        Command::Export(Export::Tiled { out_dir, area }) => export_tiled(path, &out_dir, area)?,
+        Command::Export(Export::Svg {
+            out,
+            thumbnails,
+            thumbnail_cache,
+        }) => export_svg(path, &out, thumbnails.then_some(thumbnail_cache.as_path()))?,
+        Command::Export(Export::WebMap { out_dir }) => export_web_map(path, &out_dir)?,
+        Command::Export(Export::Collision {
+            out_dir,
+            table,
+            area,
+        }) => export_collision(path, &out_dir, &table, area)?,
+        Command::ListRooms { json } => list_rooms(path, json)?,
+        Command::Info {
+            area: Some(area_id),
+            room: Some(room_id),
+            table,
+            no_color,
+        } => print_room_info(path, area_id, room_id, &table, !no_color)?,
+        Command::Info { .. } => print_info(path)?,
+        Command::Validate { json } => {
+            if !print_problems(path, json)? {
+                exit(1);
+            }
+        }
+        Command::Diff {
+            other,
+            png,
+            install,
+        } => print_diff(path, &other, png.as_deref(), install.as_deref())?,
+    }
+    Ok(())
+}
 
-    loop {
-        state.update();
-        state.draw();
-        // using vsync should prevent going too fast
+/// Loads the levels and minimap, printing anything that was wrong with them
+fn load_world(timespinner_path: &Path) -> anyhow::Result<World> {
+    eprintln!("loading levels...");
+    let mut world = World::load(timespinner_path)?;
+    eprintln!("done!");
+    print_warnings(&world.warnings());
+    if Path::new(ERAS_PATH).exists() {
+        eprintln!("loading era table...");
+        let table =
+            EraTable::load(ERAS_PATH).with_context(|| format!("couldn't load {ERAS_PATH}"))?;
+        world.set_era_table(&table);
+        eprintln!("done!");
     }
+    Ok(world)
 }
 
-struct State {
-    minimap: Minimap,
-    levels: HashMap<i32, Level>,
-    canvas: Canvas<Window>,
-    event_pump: EventPump,
-    camera: Point,
-    texture_cache: TextureCache,
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
 }
 
-impl State {
-    fn new<T: AsRef<Path>>(timespinner_path: T) -> State {
-        eprintln!("loading minimap...");
-        let minimap = Minimap::load(&timespinner_path).unwrap();
-        eprintln!("done!");
-        eprintln!("loading texture database...");
-        let texture_database = TextureDatabase::load(&timespinner_path).unwrap();
-        eprintln!("done!");
+/// Loads the levels, minimap and texture database, which is what most commands need
+fn load(timespinner_path: &Path) -> anyhow::Result<(World, TextureDatabase)> {
+    let world = load_world(timespinner_path)?;
+    eprintln!("loading texture database...");
+    let texture_database = TextureDatabase::load(timespinner_path)?;
+    eprintln!("done!");
+    Ok((world, texture_database))
+}
 
-        let mut levels = HashMap::new();
-
-        for area in &minimap.area_list.areas {
-            let filename = match area.id {
-                // TODO: should these be hardcoded? or is there a different way i should be finding these?
-                17 => "Nexus.dat",
-                18 => "Debug.dat",
-                _ => &format!("Level_{:02}.dat", area.id),
-            };
-            let mut path = timespinner_path.as_ref().to_owned();
-            path.extend(["Content", "Levels", filename]);
-            eprintln!("loading level from {path:?}...");
-            let level = load_map_from_file(path).unwrap();
-            eprintln!("done!");
-            levels.insert(area.id, level);
-        }
+/// Parses a pair of numbers separated by `separator`
+fn parse_pair(value: &str, separator: char) -> Result<(i32, i32), String> {
+    let (a, b) = value
+        .split_once(separator)
+        .ok_or_else(|| format!("expected two numbers separated by '{separator}'"))?;
+    let parse = |n: &str| n.trim().parse().map_err(|err| format!("{n:?}: {err}"));
+    Ok((parse(a)?, parse(b)?))
+}
 
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
-
-        let window = video_subsystem
-            .window(
-                "Timespinner Map",
-                (ROOM_WIDTH * TILE_SIZE) as u32,
-                (ROOM_HEIGHT * TILE_SIZE) as u32,
-            )
-            .resizable()
-            .build()
-            .unwrap();
-
-        let canvas = window
-            .into_canvas()
-            .present_vsync()
-            .accelerated()
-            .build()
-            .unwrap();
-
-        let texture_creator = canvas.texture_creator();
-        eprintln!("loading textures...");
-        let texture_cache = TextureCache::new(texture_creator, texture_database);
-        eprintln!("done!");
+fn parse_room(value: &str) -> Result<(i32, i32), String> {
+    parse_pair(value, ':')
+}
+
+fn parse_at(value: &str) -> Result<(i32, i32), String> {
+    parse_pair(value, ',')
+}
+
+fn export_tiled(timespinner_path: &Path, out_dir: &Path, area: Option<i32>) -> anyhow::Result<()> {
+    let (world, texture_database) = load(timespinner_path)?;
 
-        let event_pump = sdl_context.event_pump().unwrap();
-        State {
-            minimap,
-            levels,
-            canvas,
-            texture_cache,
-            event_pump,
-            camera: Point::new(0, 0),
+    for (id, level) in &world.levels {
+        if area.is_some_and(|area| area != *id) {
+            continue;
         }
+        eprintln!("exporting level {id}...");
+        let level_dir = out_dir.join(format!("Level_{id:02}"));
+        tiled::export_level(level, &texture_database, "./Content", level_dir)?;
+        eprintln!("done!");
     }
 
-    #[expect(clippy::match_single_binding)] // will fix later, don't want to deal with it now though
-    fn update(&mut self) {
-        for event in self.event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => exit(0),
-                Event::KeyDown {
-                    keycode: Some(keycode),
-                    ..
-                } => match keycode {
-                    _ => (),
-                },
-                Event::MouseWheel {
-                    precise_x,
-                    precise_y,
-                    ..
-                } => {
-                    self.camera.x += (precise_x * 100.0) as i32;
-                    self.camera.y -= (precise_y * 100.0) as i32;
-                }
-                Event::MouseMotion {
-                    mousestate,
-                    xrel,
-                    yrel,
-                    ..
-                } => {
-                    if mousestate.middle() || mousestate.left() {
-                        self.camera.x -= xrel;
-                        self.camera.y -= yrel;
-                    }
-                }
-                _ => (),
-            }
+    Ok(())
+}
+
+fn export_svg(
+    timespinner_path: &Path,
+    out: &Path,
+    thumbnail_cache: Option<&Path>,
+) -> anyhow::Result<()> {
+    let (world, texture_database) = load(timespinner_path)?;
+    let mut thumbnails = thumbnail_cache.map(|dir| {
+        let mut thumbnails = Thumbnails::new(Renderer::new(&texture_database, "./Content"), dir);
+        print_warnings(&thumbnails.hash_levels(&world));
+        thumbnails
+    });
+
+    eprintln!("writing svg...");
+    let (svg, warnings) = svg::world_to_svg(&world, thumbnails.as_mut())?;
+    print_warnings(&warnings);
+    std::fs::write(out, svg)?;
+    eprintln!("done!");
+
+    Ok(())
+}
+
+fn print_diff(
+    timespinner_path: &Path,
+    other: &Path,
+    png: Option<&Path>,
+    install: Option<&Path>,
+) -> anyhow::Result<()> {
+    let mut old_levels = Vec::new();
+    let mut new_levels = Vec::new();
+    let mut level_diffs = Vec::new();
+
+    if timespinner_path.is_file() && other.is_file() {
+        let old = load_map_from_file(timespinner_path)?;
+        let new = load_map_from_file(other)?;
+        let level_diff = diff::diff_levels(&old, &new);
+        if level_diff.is_empty() {
+            println!("no changes");
+        } else {
+            print!("{level_diff}");
+        }
+        old_levels.push(old);
+        new_levels.push(new);
+        level_diffs.push(level_diff);
+    } else {
+        let mut old = load_world(timespinner_path)?;
+        let mut new = load_world(other)?;
+        let world_diff = diff::diff_worlds(&old, &new);
+        print!("{world_diff}");
+        for (area, level_diff) in world_diff.changed_levels {
+            old_levels.push(old.levels.remove(&area).unwrap());
+            new_levels.push(new.levels.remove(&area).unwrap());
+            level_diffs.push(level_diff);
         }
     }
 
-    fn draw(&mut self) {
-        self.canvas.set_draw_color(Color::BLACK);
-        self.canvas.clear();
-
-        let mut camera_rect = self.canvas.viewport();
-        camera_rect.x = self.camera.x - (camera_rect.w / 2);
-        camera_rect.y = self.camera.y - (camera_rect.h / 2);
-
-        draw_grid(&mut self.canvas, camera_rect);
-
-        for area in &self.minimap.area_list.areas {
-            if let Some(level) = self.levels.get(&area.id) {
-                'rooms: for room in &area.room_list.rooms {
-                    for other in &level.rooms {
-                        if room.id == other.id {
-                            let bounding_rect = Rect::new(
-                                room.position.x * TILE_SIZE * ROOM_WIDTH,
-                                room.position.y * TILE_SIZE * ROOM_HEIGHT,
-                                other.width * TILE_SIZE as u32,
-                                other.height * TILE_SIZE as u32,
-                            );
-                            if !bounding_rect.has_intersection(camera_rect) {
-                                continue 'rooms;
-                            }
-
-                            let tileset = self.texture_cache.load_texture(&other.tileset);
-                            let frames = self.texture_cache.load_frames(&other.tileset);
-
-                            for tile in &other.bottom_tiles {
-                                draw_tile(
-                                    &mut self.canvas,
-                                    tile,
-                                    tileset,
-                                    room.position,
-                                    camera_rect,
-                                    frames,
-                                );
-                            }
-                            for tile in &other.middle_tiles {
-                                draw_tile(
-                                    &mut self.canvas,
-                                    tile,
-                                    tileset,
-                                    room.position,
-                                    camera_rect,
-                                    frames,
-                                );
-                            }
-                            for tile in &other.top_tiles {
-                                draw_tile(
-                                    &mut self.canvas,
-                                    tile,
-                                    tileset,
-                                    room.position,
-                                    camera_rect,
-                                    frames,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    let Some(png) = png else {
+        return Ok(());
+    };
+    if level_diffs.iter().all(|diff| diff.changed_rooms.is_empty()) {
+        eprintln!("no changed rooms to render");
+        return Ok(());
+    }
 
-        self.canvas.present();
+    let mut changes = Vec::new();
+    for ((old, new), level_diff) in old_levels.iter().zip(&new_levels).zip(&level_diffs) {
+        for room_diff in &level_diff.changed_rooms {
+            let old_room = old
+                .rooms
+                .iter()
+                .find(|room| room.id == room_diff.id)
+                .unwrap();
+            let new_room = new
+                .rooms
+                .iter()
+                .find(|room| room.id == room_diff.id)
+                .unwrap();
+            changes.push((old_room, new_room, room_diff));
+        }
     }
+
+    let install = match install {
+        Some(install) => install.to_owned(),
+        None if other.is_file() => find_install(other)?,
+        None => other.to_owned(),
+    };
+    let texture_database = TextureDatabase::load(&install)
+        .with_context(|| format!("couldn't load the texture database from {install:?}"))?;
+    let mut renderer = Renderer::new(&texture_database, "./Content");
+    eprintln!("rendering {} changed rooms...", changes.len());
+    diff::render_changes(&changes, &mut renderer)?.save_png(png)?;
+    eprintln!("done!");
+
+    Ok(())
+}
+
+/// Finds the installation a level file is in, which is three directories up since levels live in Content/Levels
+fn find_install(level_path: &Path) -> anyhow::Result<PathBuf> {
+    let level_path = level_path
+        .canonicalize()
+        .with_context(|| format!("couldn't find {level_path:?}"))?;
+    level_path
+        .ancestors()
+        .nth(3)
+        .filter(|install| install.join("Content").join("TextureDatabase.dat").is_file())
+        .map(Path::to_owned)
+        .with_context(|| {
+            format!("{level_path:?} isn't in an installation's Content/Levels, pass --install to say which to use")
+        })
 }
 
-fn draw_grid(canvas: &mut Canvas<Window>, camera: Rect) {
-    canvas.set_draw_color(Color::RGB(32, 32, 32));
-    let mut x = camera.x / TILE_SIZE * TILE_SIZE - camera.x;
-    if x < 0 {
-        x += TILE_SIZE;
+fn export_web_map(timespinner_path: &Path, out_dir: &Path) -> anyhow::Result<()> {
+    let (world, texture_database) = load(timespinner_path)?;
+    let mut renderer = Renderer::new(&texture_database, "./Content");
+
+    let max_zoom = web_map::export_web_map(&world, &mut renderer, out_dir, |zoom| {
+        eprintln!("rendering zoom level {zoom}...");
+    })?;
+    eprintln!("done! wrote zoom levels 0 to {max_zoom}");
+
+    Ok(())
+}
+
+/// Loads the collision table, or an empty one if the file doesn't exist so tiles go by their layer
+fn load_collision_table(path: &Path) -> anyhow::Result<CollisionTable> {
+    if !path.exists() {
+        return Ok(CollisionTable::default());
     }
-    while x < camera.w {
-        canvas.draw_line((x, 0), (x, camera.h)).unwrap();
-        x += TILE_SIZE;
+    eprintln!("loading collision table...");
+    let table = CollisionTable::load(path)?;
+    eprintln!("done!");
+    Ok(table)
+}
+
+fn export_collision(
+    timespinner_path: &Path,
+    out_dir: &Path,
+    table: &Path,
+    area: Option<i32>,
+) -> anyhow::Result<()> {
+    let world = load_world(timespinner_path)?;
+    let table = load_collision_table(table)?;
+
+    for (id, level) in &world.levels {
+        if area.is_some_and(|area| area != *id) {
+            continue;
+        }
+        eprintln!("exporting collision of level {id}...");
+        let level_dir = out_dir.join(format!("Level_{id:02}"));
+        std::fs::create_dir_all(&level_dir)?;
+        for room in &level.rooms {
+            let collision = RoomCollision::new(*id, room.id, &table.room(room));
+            std::fs::write(
+                level_dir.join(format!("Room_{:03}.json", room.id)),
+                serde_json::to_string_pretty(&collision)?,
+            )?;
+        }
+        eprintln!("done!");
     }
 
-    let mut y = camera.y / TILE_SIZE * TILE_SIZE - camera.y;
-    if y < 0 {
-        y += TILE_SIZE;
+    Ok(())
+}
+
+/// Prints every problem validation finds, returning whether there weren't any
+fn print_problems(timespinner_path: &Path, json: bool) -> anyhow::Result<bool> {
+    let (world, texture_database) = load(timespinner_path)?;
+
+    eprintln!("validating...");
+    let problems = validate::validate(&world, &texture_database);
+    eprintln!("done! found {} problems", problems.len());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&problems)?);
+    } else {
+        for problem in &problems {
+            println!("{problem}");
+        }
     }
-    while y < camera.h {
-        canvas.draw_line((0, y), (camera.w, y)).unwrap();
-        y += TILE_SIZE;
+    Ok(problems.is_empty())
+}
+
+fn render_rooms(
+    timespinner_path: &Path,
+    out_dir: &Path,
+    area: Option<i32>,
+    room: Option<(i32, i32)>,
+) -> anyhow::Result<()> {
+    let (world, texture_database) = load(timespinner_path)?;
+    let mut renderer = Renderer::new(&texture_database, "./Content");
+
+    let area = area.or(room.map(|(area_id, _)| area_id));
+    let mut rendered = 0;
+    for (id, level) in &world.levels {
+        if area.is_some_and(|area| area != *id) {
+            continue;
+        }
+        eprintln!("rendering level {id}...");
+        let level_dir = out_dir.join(format!("Level_{id:02}"));
+        std::fs::create_dir_all(&level_dir)?;
+        for level_room in &level.rooms {
+            if room.is_some_and(|(_, room_id)| room_id != level_room.id) {
+                continue;
+            }
+            renderer
+                .render_room(level_room)?
+                .save_png(level_dir.join(format!("Room_{:03}.png", level_room.id)))?;
+            rendered += 1;
+        }
+        eprintln!("done!");
     }
 
-    canvas.set_draw_color(Color::RGB(0, 64, 0));
-    let mut x = camera.x / (TILE_SIZE * ROOM_WIDTH) * (TILE_SIZE * ROOM_WIDTH) - camera.x;
-    if x < 0 {
-        x += TILE_SIZE * ROOM_WIDTH;
+    if rendered == 0 {
+        anyhow::bail!("no rooms to render");
     }
-    while x < camera.w {
-        canvas.draw_line((x, 0), (x, camera.h)).unwrap();
-        x += TILE_SIZE * ROOM_WIDTH;
+    Ok(())
+}
+
+/// A room as `list-rooms` prints it
+#[derive(Debug, Serialize)]
+struct RoomListing {
+    area_id: i32,
+    room_id: i32,
+    level_name: String,
+    room_name: String,
+    /// Size in tiles
+    width: u32,
+    height: u32,
+    background_wipe_color: Color,
+    /// Era the room is in, or `None` if it isn't on the minimap
+    era: Option<String>,
+    /// Position of the room's top left corner in pixels, or `None` if it isn't on the minimap
+    position: Option<(i32, i32)>,
+}
+
+fn list_rooms(timespinner_path: &Path, json: bool) -> anyhow::Result<()> {
+    let world = load_world(timespinner_path)?;
+
+    let mut rooms: Vec<RoomListing> = world
+        .rooms()
+        .map(|room| RoomListing {
+            area_id: room.area.id,
+            room_id: room.room.id,
+            level_name: room.level.name.clone(),
+            room_name: room.room.name.clone(),
+            width: room.room.width,
+            height: room.room.height,
+            background_wipe_color: room.room.background_wipe_color,
+            era: Some(room.era.to_string()),
+            position: Some(room.origin()),
+        })
+        .collect();
+    let unreferenced = world
+        .unreferenced_levels()
+        .into_iter()
+        .map(|level| (level, level.rooms.iter().collect()));
+    for (level, level_rooms) in world.unplaced_rooms().into_iter().chain(unreferenced) {
+        for room in level_rooms {
+            rooms.push(RoomListing {
+                area_id: level.id,
+                room_id: room.id,
+                level_name: level.name.clone(),
+                room_name: room.name.clone(),
+                width: room.width,
+                height: room.height,
+                background_wipe_color: room.background_wipe_color,
+                era: None,
+                position: None,
+            });
+        }
     }
+    rooms.sort_by_key(|room| (room.area_id, room.room_id));
 
-    let mut y = camera.y / (TILE_SIZE * ROOM_HEIGHT) * (TILE_SIZE * ROOM_HEIGHT) - camera.y;
-    if y < 0 {
-        y += TILE_SIZE * ROOM_HEIGHT;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rooms)?);
+        return Ok(());
     }
-    while y < camera.h {
-        canvas.draw_line((0, y), (camera.w, y)).unwrap();
-        y += TILE_SIZE * ROOM_HEIGHT;
+    for room in &rooms {
+        let place = match (&room.era, room.position) {
+            (Some(era), Some((x, y))) => format!("{era} at {x},{y}"),
+            _ => "not on the minimap".to_owned(),
+        };
+        println!(
+            "{}:{}\t{}\t{}\t{}x{}\t{place}",
+            room.area_id, room.room_id, room.level_name, room.room_name, room.width, room.height
+        );
     }
+    Ok(())
 }
 
-fn draw_tile(
-    canvas: &mut Canvas<Window>,
-    tile: &Tile,
-    tileset: &Texture,
-    room_pos: Point,
-    camera: Rect,
-    frames: &[Rect],
-) {
-    if tile.id >= 512 {
-        // TODO: tiles have special behaviour here
-        return;
-    }
-    let tileset_rect = frames[tile.id as usize];
+fn print_info(timespinner_path: &Path) -> anyhow::Result<()> {
+    let world = load_world(timespinner_path)?;
 
-    let screen_rect = Rect::new(
-        tile.x * TILE_SIZE - camera.x + room_pos.x * ROOM_WIDTH * TILE_SIZE,
-        tile.y * TILE_SIZE - camera.y + room_pos.y * ROOM_HEIGHT * TILE_SIZE,
-        TILE_SIZE as u32,
-        TILE_SIZE as u32,
+    let mut levels: Vec<_> = world.levels.values().collect();
+    levels.sort_by_key(|level| level.id);
+    let total: usize = levels.iter().map(|level| level.rooms.len()).sum();
+    println!(
+        "{} levels, {total} rooms ({} on the minimap)",
+        levels.len(),
+        world.rooms().count()
     );
+    if let Some(bounds) = world.bounds() {
+        println!(
+            "map is {}x{} pixels, from {},{}",
+            bounds.width(),
+            bounds.height(),
+            bounds.x(),
+            bounds.y()
+        );
+    }
 
-    canvas
-        .copy_ex(
-            tileset,
-            tileset_rect,
-            screen_rect,
-            0.0,
-            None,
-            tile.flip_x,
-            tile.flip_y,
-        )
-        .unwrap();
-}
-
-struct TextureCache {
-    textures: HashMap<String, Texture>,
-    _texture_creator: TextureCreator<WindowContext>,
-    name_to_frames: HashMap<String, Vec<Rect>>,
+    for level in levels {
+        let era = world
+            .rooms()
+            .find(|room| room.area.id == level.id)
+            .map_or("not on the minimap".to_owned(), |room| room.era.to_string());
+        println!(
+            "level {}: {} - {} rooms, {era}",
+            level.id,
+            level.name,
+            level.rooms.len()
+        );
+    }
+    for area_id in world.missing_levels() {
+        println!("area {area_id}: no level file");
+    }
+    Ok(())
 }
 
-impl TextureCache {
-    fn new(
-        texture_creator: TextureCreator<WindowContext>,
-        texture_database: TextureDatabase,
-    ) -> TextureCache {
-        let mut name_to_frames = HashMap::new();
-        let mut textures = HashMap::new();
-        for atlas in texture_database.atlases {
-            let path = format!("./Content/{}.png", &atlas.content_path);
-            textures.insert(
-                atlas.file_name.clone(),
-                texture_creator.load_texture(path).unwrap(),
-            );
-
-            // TODO: i don't think this is how frames work at all but it just happens to be correct for tiles
-            let mut frames = Vec::new();
-            for frame in atlas.frames {
-                let (mut x, mut y) = (frame.start_coordinates.x, frame.start_coordinates.y);
-                let (width, height) = (frame.frame_size.x, frame.frame_size.y);
-                let mut col = 0;
-                for _ in 0..frame.count {
-                    frames.push(Rect::new(x, y, width as u32, height as u32));
-                    x += width;
-                    col += 1;
-                    if col == frame.row_width {
-                        col = 0;
-                        if frame.does_new_row_use_start_x {
-                            x = frame.start_coordinates.x;
-                        } else {
-                            // is this correct? idk
-                            x = 0;
-                        }
-                        y += height;
-                    }
-                }
-            }
-            name_to_frames.insert(atlas.file_name, frames);
-        }
-
-        TextureCache {
-            textures,
-            _texture_creator: texture_creator,
-            name_to_frames,
+fn print_room_info(
+    timespinner_path: &Path,
+    area_id: i32,
+    room_id: i32,
+    table: &Path,
+    color: bool,
+) -> anyhow::Result<()> {
+    let world = load_world(timespinner_path)?;
+    let Some(level) = world.levels.get(&area_id) else {
+        anyhow::bail!("there's no level with area ID {area_id}");
+    };
+    let Some(room) = level.rooms.iter().find(|room| room.id == room_id) else {
+        anyhow::bail!(
+            "level {area_id} ({}) doesn't have a room {room_id}",
+            level.name
+        );
+    };
+    let collisions = load_collision_table(table)?;
+
+    println!(
+        "level {area_id} ({}) room {room_id}: {}",
+        level.name, room.name
+    );
+    println!("tileset: {}", room.tileset);
+    println!("size: {}x{} tiles", room.width, room.height);
+    println!("background wipe color: {}", room.background_wipe_color);
+    let count = |category: fn(&Category) -> bool| {
+        room.object_tiles
+            .iter()
+            .filter(|object| category(&object.category))
+            .count()
+    };
+    println!(
+        "objects: {} events, {} enemies, {} items, {} other",
+        count(|category| matches!(category, Category::Event(_))),
+        count(|category| matches!(category, Category::Enemy(_))),
+        count(|category| matches!(category, Category::Item(_))),
+        count(|category| matches!(category, Category::None)),
+    );
+    match world.room(area_id, room_id) {
+        Some(placed) => {
+            let bounds = placed.bounds();
+            println!("position: {} at {},{}", placed.era, bounds.x(), bounds.y());
         }
+        None => println!("position: not on the minimap"),
     }
-    fn load_texture(&self, name: &str) -> &Texture {
-        self.textures.get(name).unwrap()
-    }
-    fn load_frames(&self, name: &str) -> &Vec<Rect> {
-        self.name_to_frames.get(name).unwrap()
-    }
+
+    println!();
+    let color = color && std::io::stdout().is_terminal();
+    print!("{}", ascii::room_to_ascii(room, &collisions, color));
+    Ok(())
 }
@@ -1,11 +1,11 @@
 use anyhow::Result;
 use flate2::read::ZlibDecoder;
-use sdl2::rect::Point;
 use serde::Deserialize;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
+use crate::geometry::Point;
 use crate::parse_point;
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,278 @@
+use anyhow::{Result, anyhow};
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::TILE_SIZE;
+use crate::geometry::Rect;
+use crate::level::{Room, Tile};
+use crate::texture_atlas::TextureDatabase;
+
+/// An RGBA image in memory
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates a fully transparent image
+    pub fn new(width: u32, height: u32) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Index of a pixel's first channel, worked out in `usize` so large images don't overflow
+    fn pixel_index(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    pub fn load_png<T: AsRef<Path>>(path: T) -> Result<Image> {
+        let mut decoder = Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        buf.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            ColorType::Rgba => buf,
+            ColorType::Rgb => buf
+                .chunks(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            ColorType::GrayscaleAlpha => buf
+                .chunks(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            ColorType::Indexed => unreachable!("indexed images are expanded by the decoder"),
+        };
+
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        let mut encoder = Encoder::new(writer, self.width, self.height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    pub fn save_png<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+
+    /// Fills `rect` with a colour, blending it over what's already there
+    pub fn fill_rect(&mut self, rect: Rect, color: [u8; 4]) {
+        for y in rect.top().max(0)..rect.bottom().min(self.height as i32) {
+            for x in rect.left().max(0)..rect.right().min(self.width as i32) {
+                let i = self.pixel_index(x as u32, y as u32);
+                blend(&mut self.pixels[i..i + 4], &color);
+            }
+        }
+    }
+
+    /// Scales the image down to half its size, averaging each 2x2 block of pixels
+    pub fn half_size(&self) -> Image {
+        let mut half = Image::new(self.width / 2, self.height / 2);
+        for y in 0..half.height {
+            for x in 0..half.width {
+                let mut sum = [0; 3];
+                let mut alpha = 0;
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let i = self.pixel_index(x * 2 + dx, y * 2 + dy);
+                    let pixel = &self.pixels[i..i + 4];
+                    for (sum, &channel) in sum.iter_mut().zip(pixel) {
+                        *sum += channel as u32 * pixel[3] as u32;
+                    }
+                    alpha += pixel[3] as u32;
+                }
+                if alpha == 0 {
+                    continue;
+                }
+                let i = half.pixel_index(x, y);
+                for (channel, sum) in half.pixels[i..i + 3].iter_mut().zip(sum) {
+                    *channel = (sum / alpha) as u8;
+                }
+                half.pixels[i + 3] = (alpha / 4) as u8;
+            }
+        }
+        half
+    }
+
+    /// Draws `rect` from `source` with its top left corner at `(x, y)`, blending it over what's already there
+    pub fn blit(&mut self, source: &Image, rect: Rect, x: i32, y: i32, flip_x: bool, flip_y: bool) {
+        for sy in 0..rect.height() as i32 {
+            for sx in 0..rect.width() as i32 {
+                let (src_x, src_y) = (rect.x() + sx, rect.y() + sy);
+                if src_x < 0
+                    || src_y < 0
+                    || src_x >= source.width as i32
+                    || src_y >= source.height as i32
+                {
+                    continue;
+                }
+                let dx = if flip_x {
+                    rect.width() as i32 - 1 - sx
+                } else {
+                    sx
+                };
+                let dy = if flip_y {
+                    rect.height() as i32 - 1 - sy
+                } else {
+                    sy
+                };
+                let (dst_x, dst_y) = (x + dx, y + dy);
+                if dst_x < 0
+                    || dst_y < 0
+                    || dst_x >= self.width as i32
+                    || dst_y >= self.height as i32
+                {
+                    continue;
+                }
+
+                let src = source.pixel_index(src_x as u32, src_y as u32);
+                let dst = self.pixel_index(dst_x as u32, dst_y as u32);
+                blend(&mut self.pixels[dst..dst + 4], &source.pixels[src..src + 4]);
+            }
+        }
+    }
+}
+
+/// Blends `src` over `dst`, both being non-premultiplied RGBA
+fn blend(dst: &mut [u8], src: &[u8]) {
+    let src_alpha = src[3] as u32;
+    if src_alpha == 0 {
+        return;
+    }
+    if src_alpha == 255 {
+        dst.copy_from_slice(src);
+        return;
+    }
+
+    let dst_alpha = dst[3] as u32 * (255 - src_alpha) / 255;
+    let out_alpha = src_alpha + dst_alpha;
+    for i in 0..3 {
+        dst[i] = ((src[i] as u32 * src_alpha + dst[i] as u32 * dst_alpha) / out_alpha) as u8;
+    }
+    dst[3] = out_alpha as u8;
+}
+
+/// Draws rooms into images without needing a window, loading atlas images as they're needed
+pub struct Renderer<'a> {
+    texture_database: Cow<'a, TextureDatabase>,
+    content_path: PathBuf,
+    atlases: HashMap<String, (Image, Vec<Rect>)>,
+}
+
+impl<'a> Renderer<'a> {
+    /// Creates a renderer that loads atlas images from `content_path`, the directory the dumped PNGs are in
+    pub fn new<T: AsRef<Path>>(
+        texture_database: &'a TextureDatabase,
+        content_path: T,
+    ) -> Renderer<'a> {
+        Renderer {
+            texture_database: Cow::Borrowed(texture_database),
+            content_path: content_path.as_ref().to_owned(),
+            atlases: HashMap::new(),
+        }
+    }
+
+    /// Creates a renderer that keeps its own texture database, for keeping around alongside other state
+    pub fn owned<T: AsRef<Path>>(
+        texture_database: TextureDatabase,
+        content_path: T,
+    ) -> Renderer<'static> {
+        Renderer {
+            texture_database: Cow::Owned(texture_database),
+            content_path: content_path.as_ref().to_owned(),
+            atlases: HashMap::new(),
+        }
+    }
+
+    /// Forgets every atlas image loaded so far, so they're loaded again the next time they're drawn
+    pub fn clear(&mut self) {
+        self.atlases.clear();
+    }
+
+    fn load_atlas(&mut self, name: &str) -> Result<&(Image, Vec<Rect>)> {
+        if !self.atlases.contains_key(name) {
+            let atlas = self
+                .texture_database
+                .atlas(name)
+                .ok_or_else(|| anyhow!("unknown atlas {name}"))?;
+            let image = Image::load_png(
+                self.content_path
+                    .join(format!("{}.png", atlas.content_path)),
+            )?;
+            self.atlases
+                .insert(name.to_owned(), (image, atlas.frame_rects()));
+        }
+        Ok(&self.atlases[name])
+    }
+
+    /// Draws a room into `target` with the room's top left corner at `(x, y)`, filling it with its background wipe
+    /// colour first
+    ///
+    /// Tiles are drawn in the order the level file lists them, the same as the viewer, so stacked tiles all show.
+    pub fn draw_room(&mut self, target: &mut Image, room: &Room, x: i32, y: i32) -> Result<()> {
+        let (tileset, frames) = self.load_atlas(&room.tileset)?;
+        target.fill_rect(
+            Rect::new(
+                x,
+                y,
+                room.width * TILE_SIZE as u32,
+                room.height * TILE_SIZE as u32,
+            ),
+            room.background_wipe_color.to_array(),
+        );
+        for tiles in [&room.bottom_tiles, &room.middle_tiles, &room.top_tiles] {
+            for tile in tiles {
+                draw_tile(target, tile, tileset, frames, x, y);
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders a room into its own image
+    pub fn render_room(&mut self, room: &Room) -> Result<Image> {
+        let mut image = Image::new(
+            room.width * TILE_SIZE as u32,
+            room.height * TILE_SIZE as u32,
+        );
+        self.draw_room(&mut image, room, 0, 0)?;
+        Ok(image)
+    }
+}
+
+fn draw_tile(target: &mut Image, tile: &Tile, tileset: &Image, frames: &[Rect], x: i32, y: i32) {
+    if tile.id >= 512 {
+        // TODO: tiles have special behaviour here
+        return;
+    }
+    let Some(&tileset_rect) = frames.get(tile.id as usize) else {
+        return;
+    };
+
+    target.blit(
+        tileset,
+        tileset_rect,
+        x + tile.x * TILE_SIZE,
+        y + tile.y * TILE_SIZE,
+        tile.flip_x,
+        tile.flip_y,
+    );
+}
@@ -0,0 +1,121 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use quick_xml::escape::escape;
+use std::fmt::Write;
+
+use crate::thumbnail::Thumbnails;
+use crate::world::World;
+
+const FONT_SIZE: i32 = 24;
+const STROKE_WIDTH: i32 = 4;
+
+/// Colour for an area, spreading hues out so that consecutive area IDs are easy to tell apart
+fn area_color(id: i32) -> String {
+    let hue = (id as i64 * 137).rem_euclid(360);
+    format!("hsl({hue}, 60%, 45%)")
+}
+
+/// Draws the whole minimap layout as an SVG, one rectangle per room
+///
+/// Every room gets an `id` of the form `room-AREA-ROOM` and links to itself, so `map.svg#room-3-12` jumps to it. If
+/// thumbnails are given, each room's thumbnail is embedded as a PNG inside its rectangle. Rooms whose thumbnail can't
+/// be made are drawn without one, with a warning for each returned along with the SVG.
+pub fn world_to_svg(
+    world: &World,
+    mut thumbnails: Option<&mut Thumbnails>,
+) -> Result<(String, Vec<String>)> {
+    let bounds = world.bounds().ok_or_else(|| anyhow!("no rooms to draw"))?;
+
+    let mut svg = String::new();
+    let mut warnings = Vec::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        bounds.x(),
+        bounds.y(),
+        bounds.width(),
+        bounds.height(),
+        bounds.width(),
+        bounds.height(),
+    )?;
+    writeln!(
+        svg,
+        r#"<style>text {{ font-family: sans-serif; fill: white; paint-order: stroke; stroke: black; stroke-width: 3px; }}</style>"#
+    )?;
+
+    for area in &world.minimap.area_list.areas {
+        writeln!(svg, r#"<g id="area-{}" class="area">"#, area.id)?;
+        let color = area_color(area.id);
+
+        for room in world.rooms().filter(|room| room.area.id == area.id) {
+            let bounds = room.bounds();
+            let id = format!("room-{}-{}", area.id, room.room.id);
+            let label = format!("{}:{}", area.id, room.room.id);
+
+            writeln!(svg, r#"<g id="{id}" class="room">"#)?;
+            writeln!(svg, r##"<a href="#{id}">"##)?;
+            writeln!(
+                svg,
+                "<title>{} - {} ({label})</title>",
+                escape(&room.level.name),
+                escape(&room.room.name),
+            )?;
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{color}" fill-opacity="0.35" stroke="{color}" stroke-width="{STROKE_WIDTH}"/>"#,
+                bounds.x(),
+                bounds.y(),
+                bounds.width(),
+                bounds.height(),
+            )?;
+
+            let png = thumbnails.as_deref_mut().map(|thumbnails| {
+                let mut png = Vec::new();
+                thumbnails
+                    .get(room.area.id, room.room)?
+                    .write_png(&mut png)?;
+                anyhow::Ok(png)
+            });
+            match png {
+                Some(Err(err)) => warnings.push(format!(
+                    "couldn't make thumbnail of room {label}, leaving it out: {err:#}"
+                )),
+                Some(Ok(png)) => writeln!(
+                    svg,
+                    r#"<image x="{}" y="{}" width="{}" height="{}" href="data:image/png;base64,{}"/>"#,
+                    bounds.x(),
+                    bounds.y(),
+                    bounds.width(),
+                    bounds.height(),
+                    STANDARD.encode(png),
+                )?,
+                None => (),
+            }
+
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="{FONT_SIZE}">{}</text>"#,
+                bounds.x() + STROKE_WIDTH * 2,
+                bounds.y() + STROKE_WIDTH * 2 + FONT_SIZE,
+                escape(&room.room.name),
+            )?;
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="{FONT_SIZE}">{label}</text>"#,
+                bounds.x() + STROKE_WIDTH * 2,
+                bounds.y() + STROKE_WIDTH * 2 + FONT_SIZE * 2,
+            )?;
+            writeln!(svg, "</a>")?;
+            writeln!(svg, "</g>")?;
+        }
+
+        writeln!(svg, "</g>")?;
+    }
+
+    writeln!(svg, "</svg>")?;
+    if let Some(thumbnails) = thumbnails {
+        warnings.extend(thumbnails.take_warnings());
+    }
+    Ok((svg, warnings))
+}
@@ -1,13 +1,13 @@
 use anyhow::Result;
 use flate2::read::ZlibDecoder;
-use sdl2::rect::Point;
 use serde::Deserialize;
 use std::io::BufReader;
 use std::{fs::File, path::Path};
 
+use crate::geometry::{Point, Rect};
 use crate::{parse_bool, parse_point};
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct TextureDatabase {
     #[serde(rename = "Atlas")]
     pub atlases: Vec<Atlas>,
@@ -22,9 +22,13 @@ impl TextureDatabase {
         let buf_reader = BufReader::new(decoder);
         Ok(quick_xml::de::from_reader(buf_reader)?)
     }
+
+    pub fn atlas(&self, name: &str) -> Option<&Atlas> {
+        self.atlases.iter().find(|atlas| atlas.file_name == name)
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Atlas {
     #[serde(rename = "@FileName")]
     pub file_name: String,
@@ -40,7 +44,36 @@ pub struct Atlas {
     pub frames: Vec<AtlasFrame>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Atlas {
+    /// Source rectangles of every frame in the atlas, indexed by frame ID
+    pub fn frame_rects(&self) -> Vec<Rect> {
+        // TODO: i don't think this is how frames work at all but it just happens to be correct for tiles
+        let mut rects = Vec::new();
+        for frame in &self.frames {
+            let (mut x, mut y) = (frame.start_coordinates.x, frame.start_coordinates.y);
+            let (width, height) = (frame.frame_size.x, frame.frame_size.y);
+            let mut col = 0;
+            for _ in 0..frame.count {
+                rects.push(Rect::new(x, y, width as u32, height as u32));
+                x += width;
+                col += 1;
+                if col == frame.row_width {
+                    col = 0;
+                    if frame.does_new_row_use_start_x {
+                        x = frame.start_coordinates.x;
+                    } else {
+                        // is this correct? idk
+                        x = 0;
+                    }
+                    y += height;
+                }
+            }
+        }
+        rects
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct AtlasFrame {
     #[serde(rename = "@DoesNewRowUseStartX", deserialize_with = "parse_bool")]
     pub does_new_row_use_start_x: bool,
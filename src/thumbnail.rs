@@ -0,0 +1,140 @@
+use anyhow::Result;
+use flate2::Crc;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::level::Room;
+use crate::render::{Image, Renderer};
+use crate::world::World;
+
+/// How many times smaller across than its room a thumbnail is, which has to be a power of two
+pub const THUMBNAIL_SCALE: u32 = 4;
+/// Directory thumbnails are cached in unless told otherwise
+pub const CACHE_DIR: &str = "thumbnail_cache";
+
+/// Renders a room scaled down by [`THUMBNAIL_SCALE`]
+pub fn render_thumbnail(renderer: &mut Renderer, room: &Room) -> Result<Image> {
+    let mut image = renderer.render_room(room)?;
+    for _ in 0..THUMBNAIL_SCALE.trailing_zeros() {
+        image = image.half_size();
+    }
+    Ok(image)
+}
+
+/// Checksum of a file's contents, which changes whenever the file does
+pub fn hash_file<T: AsRef<Path>>(path: T) -> Result<u32> {
+    let mut crc = Crc::new();
+    crc.update(&fs::read(path)?);
+    Ok(crc.sum())
+}
+
+/// Room thumbnails, saved to disk so they're only rendered again once their level file changes
+///
+/// Thumbnails are saved as `DIR/Level_XX_HASH/Room_XXX.png`, where `HASH` is the hash of the level file they were
+/// rendered from. Changing a tileset doesn't change the hash, so delete the directory to render them again.
+pub struct Thumbnails<'a> {
+    renderer: Renderer<'a>,
+    dir: PathBuf,
+    /// Hash of every level's file, keyed by level ID
+    level_hashes: HashMap<i32, u32>,
+    /// Problems caching thumbnails since the last [`Thumbnails::take_warnings`]
+    warnings: Vec<String>,
+}
+
+impl<'a> Thumbnails<'a> {
+    /// Creates a cache in `dir`, which uses the cached thumbnails once [`Thumbnails::hash_levels`] is called
+    pub fn new<T: AsRef<Path>>(renderer: Renderer<'a>, dir: T) -> Thumbnails<'a> {
+        Thumbnails {
+            renderer,
+            dir: dir.as_ref().to_owned(),
+            level_hashes: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Hashes the file of every level in the world, to find thumbnails rendered from the same version of it
+    ///
+    /// Returns a warning for every level file that couldn't be hashed, whose thumbnails won't be cached.
+    pub fn hash_levels(&mut self, world: &World) -> Vec<String> {
+        self.level_hashes.clear();
+        let mut warnings = Vec::new();
+        for &level_id in world.levels.keys() {
+            let Some(path) = world.level_path(level_id) else {
+                continue;
+            };
+            if let Err(err) = self.hash_level(level_id, path) {
+                warnings.push(format!(
+                    "couldn't hash {path:?}, so its thumbnails won't be cached: {err:#}"
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// Hashes a level's file again after it changes
+    pub fn hash_level<T: AsRef<Path>>(&mut self, level_id: i32, path: T) -> Result<()> {
+        self.level_hashes.remove(&level_id);
+        self.level_hashes.insert(level_id, hash_file(path)?);
+        Ok(())
+    }
+
+    pub fn renderer_mut(&mut self) -> &mut Renderer<'a> {
+        &mut self.renderer
+    }
+
+    fn level_dir(&self, level_id: i32) -> Option<PathBuf> {
+        let hash = self.level_hashes.get(&level_id)?;
+        Some(self.dir.join(format!("Level_{level_id:02}_{hash:08x}")))
+    }
+
+    /// A room's thumbnail, loaded from the cache or rendered and saved into it
+    ///
+    /// Rooms of levels that couldn't be hashed are rendered every time, as are the rest of a level's rooms once one of
+    /// its thumbnails can't be saved, which adds a warning.
+    pub fn get(&mut self, level_id: i32, room: &Room) -> Result<Image> {
+        let Some(level_dir) = self.level_dir(level_id) else {
+            return render_thumbnail(&mut self.renderer, room);
+        };
+        let path = level_dir.join(format!("Room_{:03}.png", room.id));
+        if let Ok(image) = Image::load_png(&path) {
+            return Ok(image);
+        }
+
+        let image = render_thumbnail(&mut self.renderer, room)?;
+        if let Err(err) = self.save(level_id, &level_dir, &path, &image) {
+            self.level_hashes.remove(&level_id);
+            self.warnings.push(format!(
+                "couldn't cache thumbnails of level {level_id} in {level_dir:?}, so they'll be rendered every time: {err:#}"
+            ));
+        }
+        Ok(image)
+    }
+
+    fn save(&self, level_id: i32, level_dir: &Path, path: &Path, image: &Image) -> Result<()> {
+        if !level_dir.exists() {
+            self.remove_stale(level_id, level_dir);
+            fs::create_dir_all(level_dir)?;
+        }
+        image.save_png(path)
+    }
+
+    /// Problems caching thumbnails since this was last called
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Removes thumbnails rendered from older versions of a level
+    fn remove_stale(&self, level_id: i32, current: &Path) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let prefix = format!("Level_{level_id:02}_");
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path != current && entry.file_name().to_string_lossy().starts_with(&prefix) {
+                fs::remove_dir_all(path).ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::level::Tile;
+
+/// What's in a cell of a [`TileGrid`], which doesn't need the position [`Tile`] stores
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cell {
+    id: i32,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+impl Cell {
+    fn tile(self, x: i32, y: i32) -> Tile {
+        Tile {
+            id: self.id,
+            x,
+            y,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+        }
+    }
+}
+
+/// A value for every cell of a room, stored row by row from the top left
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: u32,
+    height: u32,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Creates a grid with every cell set to `value`
+    pub fn new(width: u32, height: u32, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width as usize * height as usize],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height
+    }
+
+    /// Index into [`Grid::cells`] of a cell, or `None` if it's outside of the grid
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        self.contains(x, y)
+            .then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    /// Position of the cell at an index into [`Grid::cells`]
+    pub fn position(&self, index: usize) -> (i32, i32) {
+        (
+            (index % self.width as usize) as i32,
+            (index / self.width as usize) as i32,
+        )
+    }
+
+    /// The value of a cell, or `None` if it's outside of the grid
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.index(x, y).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        self.index(x, y).map(|index| &mut self.cells[index])
+    }
+
+    /// Every cell row by row from the top left
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Every row from the top
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks can't be 0 long, but a grid 0 wide doesn't have any cells to split up anyway
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+}
+
+/// A layer of a room as a grid with a cell for every tile position, for looking tiles up by where they are
+///
+/// Level files store layers as lists of tiles, which [`TileGrid::from_tiles`] and [`TileGrid::to_tiles`] convert from
+/// and to. A grid holds at most one tile per cell and none outside of the room, so converting a list with either of
+/// those loses them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileGrid {
+    cells: Grid<Option<Cell>>,
+}
+
+impl TileGrid {
+    /// Creates an empty grid
+    pub fn new(width: u32, height: u32) -> TileGrid {
+        TileGrid {
+            cells: Grid::new(width, height, None),
+        }
+    }
+
+    /// Places a list of tiles into a grid, with later tiles replacing earlier ones in the same cell
+    pub fn from_tiles(width: u32, height: u32, tiles: &[Tile]) -> TileGrid {
+        let mut grid = TileGrid::new(width, height);
+        for tile in tiles {
+            grid.set(*tile);
+        }
+        grid
+    }
+
+    /// Every tile in the grid, row by row from the top left
+    pub fn to_tiles(&self) -> Vec<Tile> {
+        self.iter().collect()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.cells.height()
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.cells.contains(x, y)
+    }
+
+    fn tile_at(&self, index: usize) -> Option<Tile> {
+        let cell = self.cells.cells()[index]?;
+        let (x, y) = self.cells.position(index);
+        Some(cell.tile(x, y))
+    }
+
+    /// The tile at a cell, or `None` if the cell is empty or outside of the grid
+    pub fn get(&self, x: i32, y: i32) -> Option<Tile> {
+        self.cells
+            .get(x, y)
+            .copied()
+            .flatten()
+            .map(|cell| cell.tile(x, y))
+    }
+
+    /// Puts a tile in the cell at its position, returning the tile it replaced
+    ///
+    /// Tiles outside of the grid are ignored.
+    pub fn set(&mut self, tile: Tile) -> Option<Tile> {
+        let old = self.cells.get_mut(tile.x, tile.y)?.replace(Cell {
+            id: tile.id,
+            flip_x: tile.flip_x,
+            flip_y: tile.flip_y,
+        });
+        old.map(|cell| cell.tile(tile.x, tile.y))
+    }
+
+    /// Empties a cell, returning the tile that was in it
+    pub fn remove(&mut self, x: i32, y: i32) -> Option<Tile> {
+        let old = self.cells.get_mut(x, y)?.take();
+        old.map(|cell| cell.tile(x, y))
+    }
+
+    /// Every cell row by row from the top left, with `None` for empty cells
+    pub fn cells(&self) -> impl Iterator<Item = Option<Tile>> + '_ {
+        (0..self.cells.cells().len()).map(|index| self.tile_at(index))
+    }
+
+    /// Every tile in the grid, row by row from the top left
+    pub fn iter(&self) -> impl Iterator<Item = Tile> + '_ {
+        self.cells().flatten()
+    }
+}
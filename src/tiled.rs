@@ -0,0 +1,626 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::TILE_SIZE;
+use crate::geometry::Rect;
+use crate::level::{Level, ObjectTile, Room, Tile};
+use crate::level_specification::Category;
+use crate::texture_atlas::{Atlas, TextureDatabase};
+use crate::tile_grid::TileGrid;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const FLIPPED_VERTICALLY: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const FIRST_GID: u32 = 1;
+const TMX_VERSION: &str = "1.10";
+
+/// Names of the tile layers, in the same order as bottom, middle and top tiles
+const LAYER_NAMES: [&str; 3] = ["Bottom", "Middle", "Top"];
+const OBJECT_LAYER_NAME: &str = "Objects";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename = "map")]
+struct Map {
+    #[serde(rename = "@version")]
+    version: String,
+    #[serde(rename = "@orientation")]
+    orientation: String,
+    #[serde(rename = "@renderorder")]
+    render_order: String,
+    #[serde(rename = "@width")]
+    width: u32,
+    #[serde(rename = "@height")]
+    height: u32,
+    #[serde(rename = "@tilewidth")]
+    tile_width: u32,
+    #[serde(rename = "@tileheight")]
+    tile_height: u32,
+    #[serde(rename = "@infinite")]
+    infinite: u8,
+    #[serde(default, rename = "@nextlayerid")]
+    next_layer_id: u32,
+    #[serde(default, rename = "@nextobjectid")]
+    next_object_id: u32,
+    #[serde(default)]
+    properties: Properties,
+    #[serde(rename = "tileset")]
+    tilesets: Vec<TilesetReference>,
+    #[serde(default, rename = "layer")]
+    layers: Vec<Layer>,
+    #[serde(default, rename = "objectgroup")]
+    object_groups: Vec<ObjectGroup>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Properties {
+    #[serde(default, rename = "property")]
+    properties: Vec<Property>,
+}
+
+impl Properties {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| property.value.as_str())
+    }
+
+    fn require(&self, name: &str) -> Result<&str> {
+        self.get(name)
+            .ok_or_else(|| anyhow!("missing property \"{name}\""))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Property {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(default, rename = "@type", skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(default, rename = "@value")]
+    value: String,
+}
+
+impl Property {
+    fn string(name: &str, value: &str) -> Property {
+        Property {
+            name: name.to_owned(),
+            kind: None,
+            value: value.to_owned(),
+        }
+    }
+
+    fn int<T: ToString>(name: &str, value: T) -> Property {
+        Property {
+            name: name.to_owned(),
+            kind: Some("int".to_owned()),
+            value: value.to_string(),
+        }
+    }
+
+    fn bool(name: &str, value: bool) -> Property {
+        Property {
+            name: name.to_owned(),
+            kind: Some("bool".to_owned()),
+            value: value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct TilesetReference {
+    #[serde(rename = "@firstgid")]
+    first_gid: u32,
+    #[serde(rename = "@source")]
+    source: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Layer {
+    #[serde(default, rename = "@id")]
+    id: u32,
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@width")]
+    width: u32,
+    #[serde(rename = "@height")]
+    height: u32,
+    data: Data,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Data {
+    #[serde(default, rename = "@encoding")]
+    encoding: Option<String>,
+    #[serde(default, rename = "$text")]
+    text: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ObjectGroup {
+    #[serde(default, rename = "@id")]
+    id: u32,
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(default, rename = "object")]
+    objects: Vec<Object>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Object {
+    #[serde(default, rename = "@id")]
+    id: u32,
+    #[serde(default, rename = "@name")]
+    name: String,
+    #[serde(default, rename = "@type")]
+    kind: String,
+    #[serde(rename = "@x")]
+    x: f64,
+    #[serde(rename = "@y")]
+    y: f64,
+    #[serde(default, rename = "@width")]
+    width: f64,
+    #[serde(default, rename = "@height")]
+    height: f64,
+    #[serde(default)]
+    properties: Properties,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename = "tileset")]
+struct Tileset {
+    #[serde(rename = "@version")]
+    version: String,
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@tilewidth")]
+    tile_width: u32,
+    #[serde(rename = "@tileheight")]
+    tile_height: u32,
+    #[serde(rename = "@tilecount")]
+    tile_count: u32,
+    /// 0 for tilesets that give every tile its own rectangle instead of cutting an image into a grid
+    #[serde(rename = "@columns")]
+    columns: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<Image>,
+    #[serde(rename = "tile", skip_serializing_if = "Vec::is_empty")]
+    tiles: Vec<TilesetTile>,
+}
+
+/// A tile cut out of part of an image, for atlases that aren't a grid
+#[derive(Debug, Serialize)]
+struct TilesetTile {
+    #[serde(rename = "@id")]
+    id: u32,
+    #[serde(rename = "@x")]
+    x: i32,
+    #[serde(rename = "@y")]
+    y: i32,
+    #[serde(rename = "@width")]
+    width: u32,
+    #[serde(rename = "@height")]
+    height: u32,
+    image: Image,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Image {
+    #[serde(rename = "@source")]
+    source: String,
+    #[serde(rename = "@width")]
+    width: i32,
+    #[serde(rename = "@height")]
+    height: i32,
+}
+
+fn to_xml<T: Serialize>(value: &T) -> Result<String> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+    serializer.indent(' ', 1);
+    value.serialize(serializer)?;
+    xml.push('\n');
+    Ok(xml)
+}
+
+fn tile_gid(tile: &Tile) -> u32 {
+    let mut gid = tile.id as u32 + FIRST_GID;
+    if tile.flip_x {
+        gid |= FLIPPED_HORIZONTALLY;
+    }
+    if tile.flip_y {
+        gid |= FLIPPED_VERTICALLY;
+    }
+    gid
+}
+
+fn tile_layer(id: u32, name: &str, room: &Room, grid: &TileGrid) -> Layer {
+    // tiles outside of the room can't be represented in a tiled layer, and aren't in the grid either
+    let gids: Vec<u32> = grid
+        .cells()
+        .map(|cell| cell.map_or(0, |tile| tile_gid(&tile)))
+        .collect();
+
+    let rows: Vec<String> = gids
+        .chunks(room.width.max(1) as usize)
+        .map(|row| {
+            row.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect();
+
+    Layer {
+        id,
+        name: name.to_owned(),
+        width: room.width,
+        height: room.height,
+        data: Data {
+            encoding: Some("csv".to_owned()),
+            text: format!("\n{}\n", rows.join(",\n")),
+        },
+    }
+}
+
+fn object(id: u32, tile: &ObjectTile) -> Object {
+    let mut properties = vec![
+        Property::string("category", tile.category.name()),
+        Property::int("object_id", tile.category.object_id()),
+        Property::int("tile_id", tile.id),
+        Property::bool("flip_x", tile.flip_x),
+        Property::bool("flip_y", tile.flip_y),
+    ];
+    if let Some(argument) = tile.argument {
+        properties.push(Property::int("argument", argument));
+    }
+
+    let name = match tile.category {
+        crate::level::Category::None => String::new(),
+        crate::level::Category::Event(event) => format!("{event:?}"),
+        crate::level::Category::Enemy(enemy) => format!("{enemy:?}"),
+        crate::level::Category::Item(item) => format!("{item:?}"),
+    };
+
+    Object {
+        id,
+        name,
+        kind: tile.category.name().to_owned(),
+        x: (tile.x * TILE_SIZE) as f64,
+        y: (tile.y * TILE_SIZE) as f64,
+        width: TILE_SIZE as f64,
+        height: TILE_SIZE as f64,
+        properties: Properties { properties },
+    }
+}
+
+/// Converts a room into a Tiled map, using the tileset at `tileset_source`
+///
+/// Tiles outside of the room bounds are dropped, and if several tiles share a cell on the same layer only the last one
+/// is kept.
+pub fn room_to_tmx(level: &Level, room: &Room, tileset_source: &str) -> Result<String> {
+    let layers = room
+        .tile_grids()
+        .iter()
+        .zip(LAYER_NAMES)
+        .enumerate()
+        .map(|(i, (grid, name))| tile_layer(i as u32 + 1, name, room, grid))
+        .collect();
+
+    let objects: Vec<Object> = room
+        .object_tiles
+        .iter()
+        .enumerate()
+        .map(|(i, tile)| object(i as u32 + 1, tile))
+        .collect();
+
+    let map = Map {
+        version: TMX_VERSION.to_owned(),
+        orientation: "orthogonal".to_owned(),
+        render_order: "right-down".to_owned(),
+        width: room.width,
+        height: room.height,
+        tile_width: TILE_SIZE as u32,
+        tile_height: TILE_SIZE as u32,
+        infinite: 0,
+        next_layer_id: LAYER_NAMES.len() as u32 + 2,
+        next_object_id: objects.len() as u32 + 1,
+        properties: Properties {
+            properties: vec![
+                Property::int("level_id", level.id),
+                Property::string("level_name", &level.name),
+                Property::int("room_id", room.id),
+                Property::int("room_index", room.index),
+                Property::string("room_name", &room.name),
+                Property::string("tileset", &room.tileset),
+                Property::string(
+                    "background_wipe_color",
+                    &room.background_wipe_color.to_string(),
+                ),
+            ],
+        },
+        tilesets: vec![TilesetReference {
+            first_gid: FIRST_GID,
+            source: tileset_source.to_owned(),
+        }],
+        layers,
+        object_groups: vec![ObjectGroup {
+            id: LAYER_NAMES.len() as u32 + 1,
+            name: OBJECT_LAYER_NAME.to_owned(),
+            objects,
+        }],
+    };
+
+    to_xml(&map)
+}
+
+/// Whether frames are laid out the way Tiled cuts up a tileset image, all the same size and row by row from the top
+/// left without gaps
+fn is_grid(frames: &[Rect], columns: u32) -> bool {
+    let (width, height) = (frames[0].width(), frames[0].height());
+    columns > 0
+        && frames.iter().enumerate().all(|(i, frame)| {
+            let (column, row) = (i as u32 % columns, i as u32 / columns);
+            *frame
+                == Rect::new(
+                    (column * width) as i32,
+                    (row * height) as i32,
+                    width,
+                    height,
+                )
+        })
+}
+
+/// Converts an atlas into a Tiled tileset, using the image at `image_source`
+///
+/// Atlases whose frames form a grid become an ordinary tileset. Any other atlas gives each tile its own rectangle of
+/// the image, which needs Tiled 1.9 or newer.
+pub fn atlas_to_tsx(atlas: &Atlas, image_source: &str) -> Result<String> {
+    let frames = atlas.frame_rects();
+    let Some(first) = frames.first() else {
+        bail!("atlas {} has no frames", atlas.file_name);
+    };
+    if let Some(i) = frames.iter().position(Rect::is_empty) {
+        bail!("frame {i} of atlas {} has no size", atlas.file_name);
+    }
+
+    let image = Image {
+        source: image_source.to_owned(),
+        width: atlas.width,
+        height: atlas.height,
+    };
+    let columns = atlas.width as u32 / first.width();
+    let tileset = if is_grid(&frames, columns) {
+        Tileset {
+            version: TMX_VERSION.to_owned(),
+            name: atlas.file_name.clone(),
+            tile_width: first.width(),
+            tile_height: first.height(),
+            tile_count: frames.len() as u32,
+            columns,
+            image: Some(image),
+            tiles: Vec::new(),
+        }
+    } else {
+        Tileset {
+            version: TMX_VERSION.to_owned(),
+            name: atlas.file_name.clone(),
+            tile_width: frames.iter().map(Rect::width).max().unwrap_or(0),
+            tile_height: frames.iter().map(Rect::height).max().unwrap_or(0),
+            tile_count: frames.len() as u32,
+            columns: 0,
+            image: None,
+            tiles: frames
+                .iter()
+                .enumerate()
+                .map(|(i, frame)| TilesetTile {
+                    id: i as u32,
+                    x: frame.x(),
+                    y: frame.y(),
+                    width: frame.width(),
+                    height: frame.height(),
+                    image: image.clone(),
+                })
+                .collect(),
+        }
+    };
+
+    to_xml(&tileset)
+}
+
+fn parse_tiles(layer: &Layer, first_gid: u32) -> Result<TileGrid> {
+    match layer.data.encoding.as_deref() {
+        Some("csv") => (),
+        encoding => bail!(
+            "layer {} uses {:?} encoding, only csv is supported",
+            layer.name,
+            encoding
+        ),
+    }
+
+    let gids: Vec<&str> = layer
+        .data
+        .text
+        .split(',')
+        .map(str::trim)
+        .filter(|gid| !gid.is_empty())
+        .collect();
+    if layer.width == 0 && !gids.is_empty() {
+        bail!("layer {} has tiles but a width of 0", layer.name);
+    }
+    if gids.len() as u64 > layer.width as u64 * layer.height as u64 {
+        bail!(
+            "layer {} has {} tiles, more than fit in {}x{}",
+            layer.name,
+            gids.len(),
+            layer.width,
+            layer.height
+        );
+    }
+
+    let mut grid = TileGrid::new(layer.width, layer.height);
+    for (i, gid) in gids.into_iter().enumerate() {
+        let gid: u32 = gid
+            .parse()
+            .with_context(|| format!("invalid tile in layer {}", layer.name))?;
+        if gid == 0 {
+            continue;
+        }
+        let id = (gid & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY))
+            .checked_sub(first_gid)
+            .ok_or_else(|| anyhow!("tile in layer {} is not from the tileset", layer.name))?;
+        grid.set(Tile {
+            id: id as i32,
+            x: (i as u32 % layer.width) as i32,
+            y: (i as u32 / layer.width) as i32,
+            flip_x: gid & FLIPPED_HORIZONTALLY != 0,
+            flip_y: gid & FLIPPED_VERTICALLY != 0,
+        });
+    }
+    Ok(grid)
+}
+
+fn parse_object(object: &Object, map: &Map) -> Result<ObjectTile> {
+    let properties = &object.properties;
+    let category = match properties.require("category")? {
+        "None" => Category::None,
+        "Event" => Category::Event,
+        "Enemy" => Category::Enemy,
+        "Item" => Category::Item,
+        category => bail!("unknown category {category}"),
+    };
+
+    Ok(ObjectTile {
+        id: properties.require("tile_id")?.parse()?,
+        x: (object.x / map.tile_width as f64).round() as i32,
+        y: (object.y / map.tile_height as f64).round() as i32,
+        flip_x: properties.get("flip_x") == Some("true"),
+        flip_y: properties.get("flip_y") == Some("true"),
+        category: category.with_object_id(properties.require("object_id")?.parse()?)?,
+        argument: properties.get("argument").map(str::parse).transpose()?,
+    })
+}
+
+/// Converts a Tiled map created by [`room_to_tmx`] back into a room, along with the ID and name of its level
+pub fn tmx_to_room(tmx: &str) -> Result<(i32, String, Room)> {
+    let map: Map = quick_xml::de::from_str(tmx)?;
+    let first_gid = map
+        .tilesets
+        .first()
+        .ok_or_else(|| anyhow!("map has no tileset"))?
+        .first_gid;
+
+    let [bottom, middle, top] = LAYER_NAMES.map(|name| {
+        map.layers
+            .iter()
+            .find(|layer| layer.name == name)
+            .map(|layer| parse_tiles(layer, first_gid))
+            .unwrap_or_else(|| Ok(TileGrid::new(map.width, map.height)))
+    });
+
+    let mut object_tiles = Vec::new();
+    for group in &map.object_groups {
+        if group.name != OBJECT_LAYER_NAME {
+            continue;
+        }
+        for object in &group.objects {
+            object_tiles
+                .push(parse_object(object, &map).with_context(|| format!("object {}", object.id))?);
+        }
+    }
+
+    let properties = &map.properties;
+    let mut room = Room {
+        id: properties.require("room_id")?.parse()?,
+        index: properties.require("room_index")?.parse()?,
+        name: properties.require("room_name")?.to_owned(),
+        tileset: properties.require("tileset")?.to_owned(),
+        width: map.width,
+        height: map.height,
+        background_wipe_color: properties.require("background_wipe_color")?.parse()?,
+        bottom_tiles: Vec::new(),
+        middle_tiles: Vec::new(),
+        top_tiles: Vec::new(),
+        object_tiles,
+    };
+    room.set_tile_grids([bottom?, middle?, top?]);
+
+    Ok((
+        properties.require("level_id")?.parse()?,
+        properties.require("level_name")?.to_owned(),
+        room,
+    ))
+}
+
+/// Writes every room of a level as a Tiled map into `out_dir`, along with the tilesets they use
+///
+/// Tileset images are referenced from `content_path`, the directory the dumped PNGs are in.
+pub fn export_level<T: AsRef<Path>, U: AsRef<Path>>(
+    level: &Level,
+    texture_database: &TextureDatabase,
+    content_path: T,
+    out_dir: U,
+) -> Result<()> {
+    let out_dir = out_dir.as_ref();
+    let tileset_dir = out_dir.join("tilesets");
+    fs::create_dir_all(&tileset_dir)?;
+
+    let mut written_tilesets = HashSet::new();
+    for room in &level.rooms {
+        let atlas = texture_database
+            .atlas(&room.tileset)
+            .ok_or_else(|| anyhow!("room {} uses unknown tileset {}", room.id, room.tileset))?;
+
+        // tilesets are written again on every export, so ones left over from an older export don't go stale
+        let tileset_path = tileset_dir.join(format!("{}.tsx", atlas.file_name));
+        if written_tilesets.insert(&atlas.file_name) {
+            let image = content_path
+                .as_ref()
+                .join(format!("{}.png", atlas.content_path));
+            let image = std::path::absolute(&image).unwrap_or(image);
+            fs::write(
+                &tileset_path,
+                atlas_to_tsx(atlas, &image.to_string_lossy())?,
+            )?;
+        }
+
+        let tmx = room_to_tmx(level, room, &format!("tilesets/{}.tsx", atlas.file_name))?;
+        fs::write(out_dir.join(format!("Room_{:03}.tmx", room.id)), tmx)?;
+    }
+
+    Ok(())
+}
+
+/// Reads every Tiled map in `dir` back into a level, the reverse of [`export_level`]
+pub fn import_level<T: AsRef<Path>>(dir: T) -> Result<Level> {
+    let mut level: Option<Level> = None;
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "tmx") {
+            continue;
+        }
+        let (id, name, room) = tmx_to_room(&fs::read_to_string(&path)?)
+            .with_context(|| format!("failed to import {path:?}"))?;
+
+        let level = level.get_or_insert_with(|| Level {
+            id,
+            name,
+            rooms: Vec::new(),
+        });
+        if level.id != id {
+            bail!(
+                "{path:?} belongs to level {id}, expected level {}",
+                level.id
+            );
+        }
+        level.rooms.push(room);
+    }
+
+    let mut level = level.ok_or_else(|| anyhow!("no tiled maps found"))?;
+    level.rooms.sort_by_key(|room| room.index);
+    Ok(level)
+}
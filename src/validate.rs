@@ -0,0 +1,209 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::level::{Level, Room, Tile};
+use crate::texture_atlas::TextureDatabase;
+use crate::tile_grid::TileGrid;
+use crate::world::{ROOM_HEIGHT, ROOM_WIDTH, World, screens};
+
+/// What kind of problem validation found
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// A minimap area without a level file
+    MissingLevel,
+    /// A room on the minimap that isn't in its level
+    MissingRoom,
+    /// A room whose size in screens doesn't match the number of minimap cells it covers
+    SizeMismatch,
+    /// Two rooms placed so that they cover some of the same space
+    Overlap,
+    /// A room using a tileset that isn't in the texture database
+    UnknownTileset,
+    /// A tile whose ID is past the end of its tileset
+    TileOutOfRange,
+    /// A tile placed outside of its room
+    TileOutOfBounds,
+    /// More than one tile in the same cell of a tile layer
+    DuplicateCell,
+}
+
+/// Something wrong with the game data
+#[derive(Clone, Debug, Serialize)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    /// ID of the level, which is also the ID of its minimap area
+    pub level_id: i32,
+    pub room_id: Option<i32>,
+    /// Name of the layer the problem is in, for problems with tiles
+    pub layer: Option<&'static str>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "level {}", self.level_id)?;
+        if let Some(room_id) = self.room_id {
+            write!(f, " room {room_id}")?;
+        }
+        if let Some(layer) = self.layer {
+            write!(f, " {layer} layer")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Every layer of a room by name, with object tiles treated as tiles
+fn layers(room: &Room) -> [(&'static str, Vec<Tile>); 4] {
+    [
+        ("bottom", room.bottom_tiles.clone()),
+        ("middle", room.middle_tiles.clone()),
+        ("top", room.top_tiles.clone()),
+        (
+            "objects",
+            room.object_tiles.iter().cloned().map(Tile::from).collect(),
+        ),
+    ]
+}
+
+/// Checks a single room on its own
+pub fn validate_room(
+    level: &Level,
+    room: &Room,
+    texture_database: &TextureDatabase,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut problem = |kind, layer, message| {
+        problems.push(Problem {
+            kind,
+            level_id: level.id,
+            room_id: Some(room.id),
+            layer,
+            message,
+        })
+    };
+
+    let atlas = texture_database.atlas(&room.tileset);
+    if atlas.is_none() {
+        problem(
+            ProblemKind::UnknownTileset,
+            None,
+            format!("tileset {:?} isn't in the texture database", room.tileset),
+        );
+    }
+
+    for (layer, tiles) in layers(room) {
+        // placing the tiles one by one finds the ones a grid would drop, which are the problems
+        let mut grid = TileGrid::new(room.width, room.height);
+        for tile in &tiles {
+            if !grid.contains(tile.x, tile.y) {
+                problem(
+                    ProblemKind::TileOutOfBounds,
+                    Some(layer),
+                    format!(
+                        "tile at ({}, {}) is outside of the {}x{} room",
+                        tile.x, tile.y, room.width, room.height
+                    ),
+                );
+            }
+            // objects can share a cell, so only tile layers are checked
+            if layer != "objects" && grid.set(*tile).is_some() {
+                problem(
+                    ProblemKind::DuplicateCell,
+                    Some(layer),
+                    format!("more than one tile at ({}, {})", tile.x, tile.y),
+                );
+            }
+            // object tiles use sprites rather than the tileset, and IDs from 512 up have special behaviour
+            if layer == "objects" || tile.id >= 512 {
+                continue;
+            }
+            if let Some(atlas) = atlas
+                && (tile.id < 0 || tile.id >= atlas.frame_count)
+            {
+                problem(
+                    ProblemKind::TileOutOfRange,
+                    Some(layer),
+                    format!(
+                        "tile at ({}, {}) uses frame {} but {} only has {} frames",
+                        tile.x, tile.y, tile.id, atlas.file_name, atlas.frame_count
+                    ),
+                );
+            }
+        }
+    }
+    problems
+}
+
+/// Checks every level and the minimap for problems
+pub fn validate(world: &World, texture_database: &TextureDatabase) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for area_id in world.missing_levels() {
+        problems.push(Problem {
+            kind: ProblemKind::MissingLevel,
+            level_id: area_id,
+            room_id: None,
+            layer: None,
+            message: format!("minimap area {area_id} doesn't have a level"),
+        });
+    }
+
+    for area in &world.minimap.area_list.areas {
+        let Some(level) = world.levels.get(&area.id) else {
+            continue;
+        };
+        for placement in &area.room_list.rooms {
+            let Some(room) = level.rooms.iter().find(|room| room.id == placement.id) else {
+                problems.push(Problem {
+                    kind: ProblemKind::MissingRoom,
+                    level_id: level.id,
+                    room_id: Some(placement.id),
+                    layer: None,
+                    message: format!("room is on the minimap but not in {}", level.name),
+                });
+                continue;
+            };
+            let (width, height) = screens(room.width, room.height);
+            if (width, height) != (placement.width, placement.height) {
+                problems.push(Problem {
+                    kind: ProblemKind::SizeMismatch,
+                    level_id: level.id,
+                    room_id: Some(room.id),
+                    layer: None,
+                    message: format!(
+                        "room is {}x{} tiles ({width}x{height} screens of {ROOM_WIDTH}x{ROOM_HEIGHT}) but covers {}x{} minimap cells",
+                        room.width, room.height, placement.width, placement.height
+                    ),
+                });
+            }
+        }
+    }
+
+    for overlap in world.overlaps() {
+        problems.push(Problem {
+            kind: ProblemKind::Overlap,
+            level_id: overlap.a.area.id,
+            room_id: Some(overlap.a.room.id),
+            layer: None,
+            message: format!(
+                "room overlaps room {}:{} by {}x{} pixels at ({}, {})",
+                overlap.b.area.id,
+                overlap.b.room.id,
+                overlap.area.width(),
+                overlap.area.height(),
+                overlap.area.x(),
+                overlap.area.y()
+            ),
+        });
+    }
+
+    let mut levels: Vec<&Level> = world.levels.values().collect();
+    levels.sort_by_key(|level| level.id);
+    for level in levels {
+        for room in &level.rooms {
+            problems.extend(validate_room(level, room, texture_database));
+        }
+    }
+    problems
+}
@@ -0,0 +1,1691 @@
+use sdl2::EventPump;
+use sdl2::event::Event;
+use sdl2::image::LoadTexture;
+use sdl2::keyboard::{Keycode, Mod, Scancode, TextInputUtil};
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::{Duration, Instant};
+use timespinner_map::animation::Animations;
+use timespinner_map::collision::{Collision, CollisionGrid, CollisionTable};
+use timespinner_map::compare::diff_tiles;
+use timespinner_map::era::{Era, EraTable};
+use timespinner_map::render::Renderer;
+use timespinner_map::texture_atlas::TextureDatabase;
+use timespinner_map::thumbnail::{self, THUMBNAIL_SCALE, Thumbnails};
+use timespinner_map::world::{PlacedRoom, ROOM_HEIGHT, ROOM_WIDTH, World};
+use timespinner_map::{
+    TILE_SIZE,
+    level::{self, Category, Tile},
+};
+
+use bookmarks::{Bookmark, Bookmarks};
+use clock::Clock;
+use gallery::GallerySection;
+use keybindings::{Action, Keybindings};
+use layers::{Layer, Layers};
+use lru::{TEXTURE_BUDGET, TextureLru};
+use room_cache::RoomCache;
+use search::Search;
+use thumbnails::ThumbnailTextures;
+use watcher::Watcher;
+
+mod bookmarks;
+mod clock;
+mod font;
+mod gallery;
+mod keybindings;
+mod layers;
+mod loading;
+mod lru;
+mod room_cache;
+mod search;
+mod thumbnails;
+mod watcher;
+
+/// Where the camera starts
+#[derive(Clone, Copy, Debug)]
+pub enum StartAt {
+    /// Centered on a room, by area ID and room ID
+    Room(i32, i32),
+    /// At a position in pixels
+    Point(i32, i32),
+}
+
+/// Opens the viewer window and runs it until it's closed
+pub fn run<T: AsRef<Path>>(timespinner_path: T, start_at: Option<StartAt>) -> anyhow::Result<()> {
+    let mut state = State::new(timespinner_path)?;
+    match start_at {
+        Some(StartAt::Room(area_id, room_id)) => state.jump_to_room((area_id, room_id)),
+        Some(StartAt::Point(x, y)) => state.camera = Point::new(x, y),
+        None => (),
+    }
+
+    loop {
+        state.update();
+        state.draw();
+        // using vsync should prevent going too fast
+    }
+}
+
+/// Gap between eras when they're shown side by side, in pixels
+const ERA_GAP: i32 = TILE_SIZE * ROOM_WIDTH;
+/// How many pixels each pixel of an era label takes up
+const LABEL_SCALE: i32 = 4;
+/// File keybindings are loaded from, if it exists
+const KEYBINDINGS_PATH: &str = "keybindings.toml";
+/// Pixels per frame the camera moves when a pan key is first pressed
+const PAN_START_SPEED: f32 = 4.0;
+/// Pixels per frame the camera can reach by holding a pan key
+const PAN_MAX_SPEED: f32 = 48.0;
+/// How much the pan speed goes up every frame a pan key is held
+const PAN_ACCELERATION: f32 = 1.0;
+/// File animated tiles are loaded from, if it exists
+const ANIMATIONS_PATH: &str = "animations.toml";
+/// File the collision of tiles is loaded from, if it exists
+const COLLISION_PATH: &str = "collision.toml";
+/// File bookmarks are loaded from and saved to
+const BOOKMARKS_PATH: &str = "bookmarks.toml";
+/// Smallest the map can be zoomed out to
+const MIN_ZOOM: f32 = 0.125;
+/// Biggest the map can be zoomed in to
+const MAX_ZOOM: f32 = 4.0;
+/// Zoom at and below which rooms are drawn from their thumbnails instead of tile by tile
+const THUMBNAIL_ZOOM: f32 = 1.0 / THUMBNAIL_SCALE as f32;
+/// How long a room stays highlighted after jumping to it
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(2);
+/// How long the list of layers stays up after changing one
+const LAYER_PANEL_DURATION: Duration = Duration::from_secs(3);
+
+/// Which eras the viewer shows, and where
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EraView {
+    /// Every era where the minimap puts it
+    All,
+    /// A single era where the minimap puts it
+    Only(Era),
+    /// Every era next to each other, lined up at the top
+    SideBySide,
+}
+
+impl EraView {
+    fn next(self) -> EraView {
+        match self {
+            EraView::All => EraView::Only(Era::Present),
+            EraView::Only(Era::Present) => EraView::Only(Era::Past),
+            EraView::Only(Era::Past) => EraView::Only(Era::Unknown),
+            EraView::Only(Era::Unknown) => EraView::SideBySide,
+            EraView::SideBySide => EraView::All,
+        }
+    }
+
+    /// Name of the view in the bookmarks file
+    fn key(self) -> &'static str {
+        match self {
+            EraView::All => "all",
+            EraView::Only(Era::Present) => "present",
+            EraView::Only(Era::Past) => "past",
+            EraView::Only(Era::Unknown) => "unknown",
+            EraView::SideBySide => "side_by_side",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<EraView> {
+        Some(match key {
+            "all" => EraView::All,
+            "present" => EraView::Only(Era::Present),
+            "past" => EraView::Only(Era::Past),
+            "unknown" => EraView::Only(Era::Unknown),
+            "side_by_side" => EraView::SideBySide,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> String {
+        match self {
+            EraView::All => "All eras".to_owned(),
+            EraView::Only(era) => era.to_string(),
+            EraView::SideBySide => "Eras side by side".to_owned(),
+        }
+    }
+}
+
+/// How two rooms from different eras are shown when comparing them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Compare {
+    Off,
+    /// The rooms next to each other, each taking up half the window
+    Split,
+    /// The other room drawn see-through on top of the first
+    Overlay,
+}
+
+impl Compare {
+    fn next(self) -> Compare {
+        match self {
+            Compare::Off => Compare::Split,
+            Compare::Split => Compare::Overlay,
+            Compare::Overlay => Compare::Off,
+        }
+    }
+}
+
+/// Two rooms being compared
+struct Comparison {
+    a: RoomKey,
+    b: RoomKey,
+    /// Cells that differ between the rooms, in tiles
+    diff: Vec<Point>,
+}
+
+impl Comparison {
+    fn new(world: &World, a: RoomKey, b: RoomKey) -> Comparison {
+        let diff = match (world.room(a.0, a.1), world.room(b.0, b.1)) {
+            (Some(a), Some(b)) => diff_tiles(a.room, b.room)
+                .into_iter()
+                .map(Point::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Comparison { a, b, diff }
+    }
+}
+
+/// A room's area ID and its own ID
+type RoomKey = (i32, i32);
+
+/// Animations from the texture database, with the animation table on top of them if it exists
+fn load_animations(texture_database: &TextureDatabase) -> Animations {
+    let mut animations = Animations::from_texture_database(texture_database);
+    animations.extend(load_config(ANIMATIONS_PATH, "animations", Animations::load));
+    animations
+}
+
+fn load_era_table() -> EraTable {
+    load_config(crate::ERAS_PATH, "era table", EraTable::load)
+}
+
+/// Loads one of the config files the viewer reads from the directory it's run from
+///
+/// A missing file gives the default, and so does a file that can't be loaded after the error is printed, so a mistake
+/// in one doesn't stop the viewer from starting.
+fn load_config<T, F>(path: &'static str, name: &str, load: F) -> T
+where
+    T: Default,
+    F: FnOnce(&'static str) -> anyhow::Result<T>,
+{
+    if !Path::new(path).exists() {
+        return T::default();
+    }
+    eprintln!("loading {name}...");
+    match load(path) {
+        Ok(config) => {
+            eprintln!("done!");
+            config
+        }
+        Err(err) => {
+            eprintln!("couldn't load {path}, using the defaults instead: {err:#}");
+            T::default()
+        }
+    }
+}
+
+/// Bounds of every era, and how far each era is moved when shown side by side
+fn era_layout(world: &World) -> (HashMap<Era, Rect>, HashMap<Era, Point>) {
+    let era_bounds: HashMap<Era, Rect> = Era::ALL
+        .into_iter()
+        .filter_map(|era| Some((era, world.era_bounds(era)?.into())))
+        .collect();
+
+    let mut side_by_side = HashMap::new();
+    let mut next: Option<Point> = None;
+    for era in Era::ALL {
+        let Some(bounds) = era_bounds.get(&era) else {
+            continue;
+        };
+        let target = next.unwrap_or(bounds.top_left());
+        side_by_side.insert(era, target - bounds.top_left());
+        next = Some(Point::new(
+            target.x + bounds.width() as i32 + ERA_GAP,
+            target.y,
+        ));
+    }
+    (era_bounds, side_by_side)
+}
+
+struct State {
+    world: World,
+    canvas: Canvas<Window>,
+    event_pump: EventPump,
+    camera: Point,
+    texture_cache: TextureCache,
+    era_view: EraView,
+    era_bounds: HashMap<Era, Rect>,
+    /// How far each era is moved when shown side by side
+    side_by_side: HashMap<Era, Point>,
+    /// The room in the other era each room corresponds to
+    counterparts: HashMap<RoomKey, RoomKey>,
+    compare: Compare,
+    /// The rooms being compared, while comparing
+    comparing: Option<Comparison>,
+    keybindings: Keybindings,
+    /// Pixels per frame the camera moves while a pan key is held
+    pan_speed: f32,
+    text_input: TextInputUtil,
+    /// The search box, while it's open
+    search: Option<Search>,
+    /// The room last jumped to, and when
+    highlight: Option<(RoomKey, Instant)>,
+    /// How many pixels on screen each pixel of the map takes up
+    zoom: f32,
+    bookmarks: Bookmarks,
+    /// The slot and name of the bookmark being saved, while it's being named
+    naming: Option<(u32, String)>,
+    layers: Layers,
+    /// When a layer was last changed, to show the list of layers for a while after
+    layers_changed: Option<Instant>,
+    animations: Animations,
+    collisions: CollisionTable,
+    clock: Clock,
+    timespinner_path: PathBuf,
+    watcher: Watcher,
+    /// Why files that changed couldn't be loaded again, keyed by file
+    reload_errors: BTreeMap<PathBuf, String>,
+    /// Rooms that aren't on the minimap, laid out below the map
+    gallery: Vec<GallerySection>,
+    thumbnails: ThumbnailTextures,
+}
+
+/// How rooms are drawn
+#[derive(Clone, Copy)]
+struct DrawOptions<'a> {
+    layers: &'a Layers,
+    animations: &'a Animations,
+    collisions: &'a CollisionTable,
+    /// Game tick animations are drawn at
+    tick: u64,
+    /// Opacity every layer is scaled by
+    alpha: u8,
+}
+
+impl State {
+    fn new<T: AsRef<Path>>(timespinner_path: T) -> anyhow::Result<State> {
+        let sdl_context = sdl2::init().map_err(anyhow::Error::msg)?;
+        let video_subsystem = sdl_context.video().map_err(anyhow::Error::msg)?;
+
+        let window = video_subsystem
+            .window(
+                "Timespinner Map",
+                (ROOM_WIDTH * TILE_SIZE) as u32,
+                (ROOM_HEIGHT * TILE_SIZE) as u32,
+            )
+            .resizable()
+            .build()?;
+
+        let mut canvas = window.into_canvas().present_vsync().accelerated().build()?;
+        let mut event_pump = sdl_context.event_pump().map_err(anyhow::Error::msg)?;
+
+        let loaded = loading::load(&mut canvas, &mut event_pump, timespinner_path.as_ref())?;
+        let world = loaded.world;
+        let counterparts = loaded.counterparts;
+        let mut thumbnails = Thumbnails::new(
+            Renderer::owned(loaded.texture_database.clone(), "./Content"),
+            thumbnail::CACHE_DIR,
+        );
+        crate::print_warnings(&thumbnails.hash_levels(&world));
+        let thumbnails = ThumbnailTextures::new(thumbnails, canvas.texture_creator());
+        let animations = load_animations(&loaded.texture_database);
+        let texture_cache = TextureCache::new(&canvas, loaded.texture_database);
+
+        let (era_bounds, side_by_side) = era_layout(&world);
+        let gallery = gallery::layout(&world);
+
+        let keybindings = load_config(KEYBINDINGS_PATH, "keybindings", Keybindings::load);
+        let bookmarks = load_config(BOOKMARKS_PATH, "bookmarks", Bookmarks::load);
+        let collisions = load_config(COLLISION_PATH, "collision table", CollisionTable::load);
+
+        let timespinner_path = timespinner_path.as_ref().to_owned();
+        let watcher = Watcher::new(vec![
+            (timespinner_path.join("Content").join("Levels"), false),
+            (timespinner_path.join("Content"), false),
+            (PathBuf::from("./Content"), true),
+        ]);
+
+        // only typed into the search box and bookmark names
+        let text_input = video_subsystem.text_input();
+        text_input.stop();
+
+        Ok(State {
+            world,
+            canvas,
+            texture_cache,
+            event_pump,
+            camera: Point::new(0, 0),
+            era_view: EraView::All,
+            era_bounds,
+            side_by_side,
+            counterparts,
+            compare: Compare::Off,
+            comparing: None,
+            keybindings,
+            pan_speed: PAN_START_SPEED,
+            text_input,
+            search: None,
+            highlight: None,
+            zoom: 1.0,
+            bookmarks,
+            naming: None,
+            layers: Layers::default(),
+            layers_changed: None,
+            animations,
+            collisions,
+            clock: Clock::new(),
+            timespinner_path,
+            watcher,
+            reload_errors: BTreeMap::new(),
+            gallery,
+            thumbnails,
+        })
+    }
+
+    /// Loads files that changed on disk again, keeping the camera where it is
+    fn reload_changed(&mut self) {
+        for path in self.watcher.changed() {
+            let content = self.timespinner_path.join("Content");
+            let result = if path == content.join("TextureDatabase.dat") {
+                eprintln!("reloading texture database...");
+                TextureDatabase::load(&self.timespinner_path).map(|texture_database| {
+                    self.animations = load_animations(&texture_database);
+                    *self.thumbnails.thumbnails_mut().renderer_mut() =
+                        Renderer::owned(texture_database.clone(), "./Content");
+                    self.thumbnails.clear();
+                    self.texture_cache.set_database(texture_database);
+                })
+            } else if path == content.join("Levels").join("Minimap.dat") {
+                eprintln!("reloading every level...");
+                World::load(&self.timespinner_path).map(|mut world| {
+                    world.set_era_table(&load_era_table());
+                    self.world = world;
+                    crate::print_warnings(&self.world.warnings());
+                    crate::print_warnings(
+                        &self.thumbnails.thumbnails_mut().hash_levels(&self.world),
+                    );
+                    self.thumbnails.clear();
+                    self.texture_cache.rooms.clear();
+                    self.refresh_world();
+                })
+            } else if path.extension().is_some_and(|extension| extension == "png") {
+                self.texture_cache.reload(&path);
+                self.thumbnails.thumbnails_mut().renderer_mut().clear();
+                self.thumbnails.clear();
+                Ok(())
+            } else if path.parent() == Some(&content.join("Levels"))
+                && path.extension().is_some_and(|extension| extension == "dat")
+            {
+                eprintln!("reloading level from {path:?}...");
+                self.world.reload_level(&path).and_then(|level_id| {
+                    self.thumbnails.forget_level(level_id);
+                    self.texture_cache.rooms.forget_level(level_id);
+                    self.refresh_world();
+                    self.thumbnails.thumbnails_mut().hash_level(level_id, &path)
+                })
+            } else {
+                continue;
+            };
+
+            match result {
+                Ok(()) => {
+                    eprintln!("done!");
+                    self.reload_errors.remove(&path);
+                }
+                Err(err) => {
+                    eprintln!("couldn't reload {path:?}: {err:#}");
+                    self.reload_errors.insert(path, format!("{err:#}"));
+                }
+            }
+        }
+    }
+
+    /// Works out everything that depends on the world again after it changes
+    fn refresh_world(&mut self) {
+        (self.era_bounds, self.side_by_side) = era_layout(&self.world);
+        self.gallery = gallery::layout(&self.world);
+        self.counterparts = loading::find_counterparts(&self.world);
+        if let Some(comparison) = &mut self.comparing {
+            *comparison = Comparison::new(&self.world, comparison.a, comparison.b);
+        }
+    }
+
+    /// How far rooms of an era are moved from where the minimap puts them, or `None` if the era is hidden
+    fn era_offset(&self, era: Era) -> Option<Point> {
+        match self.era_view {
+            EraView::All => Some(Point::new(0, 0)),
+            EraView::Only(only) => (only == era).then_some(Point::new(0, 0)),
+            EraView::SideBySide => self.side_by_side.get(&era).copied(),
+        }
+    }
+
+    /// The visible room at a point, taking the era view into account
+    fn room_at(&self, point: Point) -> Option<PlacedRoom<'_>> {
+        self.world.rooms().find(|room| {
+            self.era_offset(room.era).is_some_and(|offset| {
+                let mut bounds: Rect = room.bounds().into();
+                bounds.offset(offset.x, offset.y);
+                bounds.contains_point(point)
+            })
+        })
+    }
+
+    /// Switches to the next comparison mode, comparing the room in the middle of the window with its counterpart
+    fn cycle_compare(&mut self) {
+        let compare = self.compare.next();
+        if compare == Compare::Off {
+            self.compare = compare;
+            self.comparing = None;
+            return;
+        }
+
+        if self.comparing.is_none() {
+            let Some(room) = self.room_at(self.camera) else {
+                eprintln!("no room to compare here");
+                return;
+            };
+            let key = (room.area.id, room.room.id);
+            let Some(&counterpart) = self.counterparts.get(&key) else {
+                eprintln!(
+                    "room {}:{} doesn't have a counterpart in another era",
+                    key.0, key.1
+                );
+                return;
+            };
+            self.comparing = Some(Comparison::new(&self.world, key, counterpart));
+        }
+        self.compare = compare;
+    }
+
+    fn set_era_view(&mut self, era_view: EraView) {
+        self.era_view = era_view;
+        self.canvas
+            .window_mut()
+            .set_title(&format!("Timespinner Map - {}", era_view.name()))
+            .unwrap();
+    }
+
+    /// The era the camera is looking at
+    fn current_era(&self) -> Era {
+        if let EraView::Only(era) = self.era_view {
+            return era;
+        }
+        if let Some(room) = self.room_at(self.camera) {
+            return room.era;
+        }
+        Era::ALL
+            .into_iter()
+            .find(|&era| {
+                let (Some(bounds), Some(offset)) =
+                    (self.era_bounds.get(&era), self.era_offset(era))
+                else {
+                    return false;
+                };
+                let mut bounds = *bounds;
+                bounds.offset(offset.x, offset.y);
+                bounds.contains_point(self.camera)
+            })
+            .unwrap_or(Era::Present)
+    }
+
+    /// Moves the camera to the era `step` eras after the current one, keeping its place relative to the era
+    fn jump_era(&mut self, step: isize) {
+        let eras: Vec<Era> = Era::ALL
+            .into_iter()
+            .filter(|era| self.era_bounds.contains_key(era))
+            .collect();
+        let current = self.current_era();
+        let Some(index) = eras.iter().position(|&era| era == current) else {
+            return;
+        };
+        let next = eras[(index as isize + step).rem_euclid(eras.len() as isize) as usize];
+
+        let relative = self.camera
+            - self.era_bounds[&current].top_left()
+            - self.era_offset(current).unwrap_or(Point::new(0, 0));
+        if let EraView::Only(_) = self.era_view {
+            self.set_era_view(EraView::Only(next));
+        }
+        self.camera = relative
+            + self.era_bounds[&next].top_left()
+            + self.era_offset(next).unwrap_or(Point::new(0, 0));
+    }
+
+    /// Centers the camera on an area, showing its era if it's hidden
+    fn jump_to_area(&mut self, area_id: i32) {
+        let Some(bounds) = self.world.area_bounds(area_id).map(Rect::from) else {
+            eprintln!("area {area_id} doesn't have any rooms");
+            return;
+        };
+        let era = self.world.era(area_id);
+        if self.era_offset(era).is_none() {
+            self.set_era_view(EraView::Only(era));
+        }
+        self.camera = bounds.center() + self.era_offset(era).unwrap_or(Point::new(0, 0));
+    }
+
+    /// Centers the camera on a room and highlights it, showing its era if it's hidden
+    fn jump_to_room(&mut self, key: RoomKey) {
+        let Some(room) = self.world.room(key.0, key.1) else {
+            eprintln!("room {}:{} isn't on the map", key.0, key.1);
+            return;
+        };
+        let (era, center) = (room.era, Point::from(room.bounds().center()));
+        if self.era_offset(era).is_none() {
+            self.set_era_view(EraView::Only(era));
+        }
+        self.compare = Compare::Off;
+        self.comparing = None;
+        self.camera = center + self.era_offset(era).unwrap_or(Point::new(0, 0));
+        self.highlight = Some((key, Instant::now()));
+    }
+
+    fn open_search(&mut self) {
+        self.search = Some(Search::default());
+        self.text_input.start();
+    }
+
+    fn close_search(&mut self) {
+        self.search = None;
+        self.text_input.stop();
+    }
+
+    /// Handles an event while the search box is open
+    fn update_search(&mut self, event: Event) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        match event {
+            Event::TextInput { text, .. } => search.push(&self.world, &text),
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::ESCAPE => self.close_search(),
+                Keycode::BACKSPACE => search.pop(&self.world),
+                Keycode::UP => search.select(-1),
+                Keycode::DOWN => search.select(1),
+                Keycode::RETURN | Keycode::KP_ENTER => {
+                    let selection = search.selection();
+                    self.close_search();
+                    if let Some(key) = selection {
+                        self.jump_to_room(key);
+                    }
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    /// Moves to a saved view
+    fn recall_bookmark(&mut self, slot: u32) {
+        let Some(bookmark) = self.bookmarks.get(slot).cloned() else {
+            eprintln!("no bookmark saved in slot {slot}");
+            return;
+        };
+        self.compare = Compare::Off;
+        self.comparing = None;
+        self.camera = Point::new(bookmark.x, bookmark.y);
+        self.zoom = bookmark.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        match EraView::from_key(&bookmark.era_view) {
+            Some(era_view) => self.set_era_view(era_view),
+            None => eprintln!("unknown era view {}", bookmark.era_view),
+        }
+        for key in self.layers.restore(&bookmark.layers) {
+            eprintln!("unknown layer {key}");
+        }
+    }
+
+    /// Starts naming a bookmark to save the current view to
+    fn start_naming(&mut self, slot: u32) {
+        let name = match self.bookmarks.get(slot) {
+            Some(bookmark) => bookmark.name.clone(),
+            None => format!("Bookmark {slot}"),
+        };
+        self.naming = Some((slot, name));
+        self.text_input.start();
+    }
+
+    fn save_bookmark(&mut self, slot: u32, name: String) {
+        self.bookmarks.set(Bookmark {
+            slot,
+            name,
+            x: self.camera.x,
+            y: self.camera.y,
+            zoom: self.zoom,
+            era_view: self.era_view.key().to_owned(),
+            layers: self.layers.save(),
+        });
+        match self.bookmarks.save(BOOKMARKS_PATH) {
+            Ok(()) => eprintln!("saved bookmark {slot} to {BOOKMARKS_PATH}"),
+            Err(err) => eprintln!("couldn't save bookmarks: {err}"),
+        }
+    }
+
+    /// Handles an event while a bookmark is being named
+    fn update_naming(&mut self, event: Event) {
+        let Some((slot, name)) = &mut self.naming else {
+            return;
+        };
+        match event {
+            Event::TextInput { text, .. } => name.push_str(&text),
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::ESCAPE => {
+                    self.naming = None;
+                    self.text_input.stop();
+                }
+                Keycode::BACKSPACE => {
+                    name.pop();
+                }
+                Keycode::RETURN | Keycode::KP_ENTER => {
+                    let (slot, name) = (*slot, name.clone());
+                    self.naming = None;
+                    self.text_input.stop();
+                    self.save_bookmark(slot, name);
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    fn run_action(&mut self, action: Action, keymod: Mod) {
+        let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+        match action {
+            Action::PreviousEra => self.jump_era(-1),
+            Action::NextEra => self.jump_era(1),
+            Action::Home => self.camera = Point::new(0, 0),
+            Action::CycleEraView => self.set_era_view(self.era_view.next()),
+            Action::CycleCompare => self.cycle_compare(),
+            Action::Search => self.open_search(),
+            Action::ZoomIn => self.zoom = (self.zoom * 2.0).min(MAX_ZOOM),
+            Action::ZoomOut => self.zoom = (self.zoom / 2.0).max(MIN_ZOOM),
+            Action::TogglePause => self.clock.toggle_pause(),
+            Action::StepAnimation => self.clock.step(),
+            Action::NextLayer => {
+                self.layers.select_next();
+                self.layers_changed = Some(Instant::now());
+            }
+            Action::ToggleLayer => {
+                self.layers.toggle_selected();
+                self.layers_changed = Some(Instant::now());
+            }
+            Action::LayerOpacityUp | Action::LayerOpacityDown => {
+                self.layers.change_opacity(action == Action::LayerOpacityUp);
+                self.layers_changed = Some(Instant::now());
+            }
+            Action::Bookmark(slot) if shift => self.start_naming(slot),
+            Action::Bookmark(slot) => self.recall_bookmark(slot),
+            Action::JumpToArea(area_id) => {
+                self.jump_to_area(if shift { area_id + 10 } else { area_id });
+            }
+            // panning is handled every frame while the key is held
+            Action::PanLeft | Action::PanRight | Action::PanUp | Action::PanDown => (),
+        }
+    }
+
+    /// Moves the camera while pan keys are held, speeding up the longer they're held
+    fn pan(&mut self) {
+        if self.search.is_some() || self.naming.is_some() {
+            self.pan_speed = PAN_START_SPEED;
+            return;
+        }
+        let keyboard_state = self.event_pump.keyboard_state();
+        let held = |action| {
+            self.keybindings.keys(action).any(|keycode| {
+                Scancode::from_keycode(keycode)
+                    .is_some_and(|scancode| keyboard_state.is_scancode_pressed(scancode))
+            })
+        };
+        let x = held(Action::PanRight) as i32 - held(Action::PanLeft) as i32;
+        let y = held(Action::PanDown) as i32 - held(Action::PanUp) as i32;
+
+        if x == 0 && y == 0 {
+            self.pan_speed = PAN_START_SPEED;
+            return;
+        }
+        let speed = (self.pan_speed / self.zoom) as i32;
+        self.camera += Point::new(x * speed, y * speed);
+        self.pan_speed = (self.pan_speed + PAN_ACCELERATION).min(PAN_MAX_SPEED);
+    }
+
+    fn update(&mut self) {
+        let events: Vec<Event> = self.event_pump.poll_iter().collect();
+        for event in events {
+            match event {
+                Event::Quit { .. } => exit(0),
+                // textures drawn into can be lost along with the graphics device
+                Event::RenderTargetsReset { .. } | Event::RenderDeviceReset { .. } => {
+                    self.texture_cache.rooms.clear()
+                }
+                Event::KeyDown { .. } | Event::TextInput { .. } if self.search.is_some() => {
+                    self.update_search(event)
+                }
+                Event::KeyDown { .. } | Event::TextInput { .. } if self.naming.is_some() => {
+                    self.update_naming(event)
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(action) = self.keybindings.action(keycode) {
+                        self.run_action(action, keymod);
+                    }
+                }
+                Event::MouseWheel {
+                    precise_x,
+                    precise_y,
+                    ..
+                } => {
+                    self.camera.x += (precise_x * 100.0 / self.zoom) as i32;
+                    self.camera.y -= (precise_y * 100.0 / self.zoom) as i32;
+                }
+                Event::MouseMotion {
+                    mousestate,
+                    xrel,
+                    yrel,
+                    ..
+                } if mousestate.middle() || mousestate.left() => {
+                    self.camera.x -= (xrel as f32 / self.zoom) as i32;
+                    self.camera.y -= (yrel as f32 / self.zoom) as i32;
+                }
+                _ => (),
+            }
+        }
+        self.pan();
+        self.clock.update();
+        self.reload_changed();
+    }
+
+    fn draw(&mut self) {
+        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.clear();
+        // the viewport is in zoomed pixels once the scale is set
+        self.canvas.set_scale(self.zoom, self.zoom).unwrap();
+
+        let mut camera_rect = self.canvas.viewport();
+        camera_rect.x = self.camera.x - (camera_rect.w / 2);
+        camera_rect.y = self.camera.y - (camera_rect.h / 2);
+
+        if let Some(alpha) = self.layers.alpha(Layer::Grid) {
+            draw_grid(&mut self.canvas, camera_rect, alpha);
+        }
+
+        if self.comparing.is_some() {
+            self.draw_comparison(camera_rect);
+        } else {
+            self.draw_world(camera_rect);
+        }
+        self.draw_highlight(camera_rect);
+
+        self.canvas.set_scale(1.0, 1.0).unwrap();
+        if self.clock.paused() {
+            let label = format!("Paused - tick {}", self.clock.tick());
+            let y = self.canvas.viewport().height() as i32 - (font::GLYPH_HEIGHT + 4) * 2;
+            draw_text(&mut self.canvas, &label, 8, y, 2, Color::WHITE);
+        }
+        if !self.reload_errors.is_empty() {
+            let lines: Vec<(String, Color)> = self
+                .reload_errors
+                .iter()
+                .map(|(path, err)| (format!("{}: {err}", path.display()), Color::RED))
+                .collect();
+            draw_text_box_bottom(&mut self.canvas, &lines);
+        }
+        self.draw_search();
+        if let Some((slot, name)) = &self.naming {
+            let lines = [(format!("Bookmark {slot} name: {name}_"), Color::WHITE)];
+            draw_text_box(&mut self.canvas, &lines);
+        } else if self.search.is_none() {
+            self.draw_layer_panel();
+        }
+
+        self.canvas.present();
+        self.texture_cache.end_frame();
+        self.thumbnails.end_frame();
+    }
+
+    fn draw_world(&mut self, camera_rect: Rect) {
+        let minimap_blocks = self
+            .layers
+            .alpha(Layer::MinimapBlocks)
+            .map(|alpha| (self.minimap_blocks(camera_rect), alpha));
+
+        // every visible room, with the ID of its level and where it's drawn
+        let mut visible = Vec::new();
+        for room in self.world.rooms() {
+            let Some(offset) = self.era_offset(room.era) else {
+                continue;
+            };
+            let mut bounding_rect: Rect = room.bounds().into();
+            bounding_rect.offset(offset.x, offset.y);
+            if bounding_rect.has_intersection(camera_rect) {
+                visible.push((room.area.id, room.room, bounding_rect.top_left()));
+            }
+        }
+        for section in &self.gallery {
+            for gallery_room in &section.rooms {
+                if !gallery_room.bounds.has_intersection(camera_rect) {
+                    continue;
+                }
+                let room = self
+                    .world
+                    .levels
+                    .get(&gallery_room.level_id)
+                    .and_then(|level| {
+                        level
+                            .rooms
+                            .iter()
+                            .find(|room| room.id == gallery_room.room_id)
+                    });
+                if let Some(room) = room {
+                    visible.push((gallery_room.level_id, room, gallery_room.bounds.top_left()));
+                }
+            }
+        }
+
+        let options = DrawOptions {
+            layers: &self.layers,
+            animations: &self.animations,
+            collisions: &self.collisions,
+            tick: self.clock.tick(),
+            alpha: 255,
+        };
+        // thumbnails have the backdrop and every tile layer baked in, so they can only stand in while those are all
+        // fully shown and the minimap blocks, which go between them, are hidden
+        let use_thumbnails = self.zoom <= THUMBNAIL_ZOOM
+            && minimap_blocks.is_none()
+            && [Layer::Backdrop, Layer::Bottom, Layer::Middle, Layer::Top]
+                .iter()
+                .all(|&layer| self.layers.alpha(layer) == Some(255));
+
+        if !use_thumbnails {
+            for &(_, room, origin) in &visible {
+                draw_backdrop(&mut self.canvas, room, origin, camera_rect, options);
+            }
+        }
+        if let Some((rects, alpha)) = minimap_blocks {
+            draw_minimap_blocks(&mut self.canvas, &rects, alpha);
+        }
+
+        for (level_id, room, origin) in visible {
+            let thumbnail = if use_thumbnails {
+                self.thumbnails.get(level_id, room)
+            } else {
+                None
+            };
+            match thumbnail {
+                Some(texture) => {
+                    let rect = Rect::new(
+                        origin.x - camera_rect.x,
+                        origin.y - camera_rect.y,
+                        room.width * TILE_SIZE as u32,
+                        room.height * TILE_SIZE as u32,
+                    );
+                    self.canvas.copy(texture, None, rect).unwrap();
+                    draw_room_overlays(
+                        &mut self.canvas,
+                        &mut self.texture_cache.rooms,
+                        level_id,
+                        room,
+                        origin,
+                        camera_rect,
+                        options,
+                    );
+                }
+                None => {
+                    if use_thumbnails {
+                        draw_backdrop(&mut self.canvas, room, origin, camera_rect, options);
+                    }
+                    draw_room(
+                        &mut self.canvas,
+                        &mut self.texture_cache,
+                        level_id,
+                        room,
+                        origin,
+                        camera_rect,
+                        options,
+                    );
+                }
+            }
+        }
+
+        let Some(alpha) = self.layers.alpha(Layer::Labels) else {
+            return;
+        };
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        for section in &self.gallery {
+            draw_text(
+                &mut self.canvas,
+                &section.label,
+                section.label_position.x - camera_rect.x,
+                section.label_position.y - camera_rect.y,
+                LABEL_SCALE,
+                Color::RGBA(255, 255, 255, alpha),
+            );
+        }
+        for (&era, bounds) in &self.era_bounds {
+            let Some(offset) = self.era_offset(era) else {
+                continue;
+            };
+            draw_text(
+                &mut self.canvas,
+                era.name(),
+                bounds.x() + offset.x - camera_rect.x,
+                bounds.y() + offset.y - camera_rect.y - (font::GLYPH_HEIGHT + 2) * LABEL_SCALE,
+                LABEL_SCALE,
+                Color::RGBA(255, 255, 255, alpha),
+            );
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+    }
+
+    /// The cells the minimap covers for every room, relative to the camera
+    fn minimap_blocks(&self, camera_rect: Rect) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        for room in self.world.rooms() {
+            let Some(offset) = self.era_offset(room.era) else {
+                continue;
+            };
+            let mut rect: Rect = room.minimap_bounds().into();
+            rect.offset(offset.x - camera_rect.x, offset.y - camera_rect.y);
+            if rect.has_intersection(Rect::new(0, 0, camera_rect.width(), camera_rect.height())) {
+                rects.push(rect);
+            }
+        }
+        rects
+    }
+
+    /// Lists every layer for a while after one is changed
+    fn draw_layer_panel(&mut self) {
+        if self
+            .layers_changed
+            .is_none_or(|time| time.elapsed() >= LAYER_PANEL_DURATION)
+        {
+            return;
+        }
+        let selected = self.layers.selected();
+        let lines: Vec<(String, Color)> = self
+            .layers
+            .describe()
+            .into_iter()
+            .map(|(layer, line)| {
+                let color = if layer == selected {
+                    Color::YELLOW
+                } else {
+                    Color::GRAY
+                };
+                (line, color)
+            })
+            .collect();
+        draw_text_box(&mut self.canvas, &lines);
+    }
+
+    /// Outlines the room last jumped to, fading out over time
+    fn draw_highlight(&mut self, camera_rect: Rect) {
+        let Some((key, time)) = self.highlight else {
+            return;
+        };
+        let elapsed = time.elapsed();
+        if elapsed >= HIGHLIGHT_DURATION || self.comparing.is_some() {
+            self.highlight = None;
+            return;
+        }
+        let Some(room) = self.world.room(key.0, key.1) else {
+            return;
+        };
+        let Some(offset) = self.era_offset(room.era) else {
+            return;
+        };
+        let mut bounds = room.bounds();
+        bounds.offset(offset.x - camera_rect.x, offset.y - camera_rect.y);
+
+        let alpha = 255.0 * (1.0 - elapsed.as_secs_f32() / HIGHLIGHT_DURATION.as_secs_f32());
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas
+            .set_draw_color(Color::RGBA(255, 255, 0, alpha as u8));
+        for inset in 0..4 {
+            let rect = Rect::new(
+                bounds.x() + inset,
+                bounds.y() + inset,
+                bounds.width().saturating_sub(inset as u32 * 2),
+                bounds.height().saturating_sub(inset as u32 * 2),
+            );
+            self.canvas.draw_rect(rect).unwrap();
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+    }
+
+    /// Draws the search box and its results over the top of the window
+    fn draw_search(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let mut lines = vec![(format!("> {}_", search.query), Color::WHITE)];
+        for (i, (_, label)) in search.results.iter().take(search::MAX_RESULTS).enumerate() {
+            let color = if i == search.selected {
+                Color::YELLOW
+            } else {
+                Color::GRAY
+            };
+            lines.push((label.clone(), color));
+        }
+        draw_text_box(&mut self.canvas, &lines);
+    }
+
+    fn draw_comparison(&mut self, camera_rect: Rect) {
+        let Some(comparison) = &self.comparing else {
+            return;
+        };
+        let (Some(a), Some(b)) = (
+            self.world.room(comparison.a.0, comparison.a.1),
+            self.world.room(comparison.b.0, comparison.b.1),
+        ) else {
+            return;
+        };
+        // both rooms are drawn where the first one is, so the camera stays where it was
+        let offset = self.era_offset(a.era).unwrap_or(Point::new(0, 0));
+        let origin = Point::from(a.bounds().top_left()) + offset;
+        let options = DrawOptions {
+            layers: &self.layers,
+            animations: &self.animations,
+            collisions: &self.collisions,
+            tick: self.clock.tick(),
+            alpha: 255,
+        };
+
+        match self.compare {
+            Compare::Off => (),
+            Compare::Split => {
+                let half = camera_rect.w / 2;
+                let mut left_camera = camera_rect;
+                left_camera.x = self.camera.x - half / 2;
+                let mut right_camera = left_camera;
+                right_camera.x -= half;
+
+                for (room, camera, clip) in [
+                    (
+                        a,
+                        left_camera,
+                        Rect::new(0, 0, half as u32, camera_rect.height()),
+                    ),
+                    (
+                        b,
+                        right_camera,
+                        Rect::new(half, 0, (camera_rect.w - half) as u32, camera_rect.height()),
+                    ),
+                ] {
+                    self.canvas.set_clip_rect(clip);
+                    draw_backdrop(&mut self.canvas, room.room, origin, camera, options);
+                    draw_room(
+                        &mut self.canvas,
+                        &mut self.texture_cache,
+                        room.area.id,
+                        room.room,
+                        origin,
+                        camera,
+                        options,
+                    );
+                    draw_diff(&mut self.canvas, &comparison.diff, origin, camera);
+                }
+                self.canvas.set_clip_rect(None);
+
+                self.canvas.set_draw_color(Color::WHITE);
+                self.canvas
+                    .draw_line((half, 0), (half, camera_rect.h))
+                    .unwrap();
+            }
+            Compare::Overlay => {
+                draw_backdrop(&mut self.canvas, a.room, origin, camera_rect, options);
+                draw_room(
+                    &mut self.canvas,
+                    &mut self.texture_cache,
+                    a.area.id,
+                    a.room,
+                    origin,
+                    camera_rect,
+                    options,
+                );
+                draw_room(
+                    &mut self.canvas,
+                    &mut self.texture_cache,
+                    b.area.id,
+                    b.room,
+                    origin,
+                    camera_rect,
+                    DrawOptions {
+                        alpha: 128,
+                        ..options
+                    },
+                );
+                draw_diff(&mut self.canvas, &comparison.diff, origin, camera_rect);
+            }
+        }
+
+        let label = format!(
+            "{} {}:{} - {} {}:{}",
+            a.era, a.area.id, a.room.id, b.era, b.area.id, b.room.id
+        );
+        draw_text(&mut self.canvas, &label, 8, 8, 2, Color::WHITE);
+    }
+}
+
+/// Shades the cells the minimap covers
+fn draw_minimap_blocks(canvas: &mut Canvas<Window>, rects: &[Rect], alpha: u8) {
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(40, 80, 200, alpha / 3));
+    canvas.fill_rects(rects).unwrap();
+    canvas.set_draw_color(Color::RGBA(80, 140, 255, alpha));
+    canvas.draw_rects(rects).unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+/// Fills a room with its background wipe colour, which shows wherever it doesn't have tiles
+fn draw_backdrop(
+    canvas: &mut Canvas<Window>,
+    room: &level::Room,
+    origin: Point,
+    camera: Rect,
+    options: DrawOptions,
+) {
+    let Some(layer_alpha) = options.layers.alpha(Layer::Backdrop) else {
+        return;
+    };
+    let mut color: Color = room.background_wipe_color.into();
+    color.a = (color.a as u32 * layer_alpha as u32 * options.alpha as u32 / (255 * 255)) as u8;
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(color);
+    canvas
+        .fill_rect(Rect::new(
+            origin.x - camera.x,
+            origin.y - camera.y,
+            room.width * TILE_SIZE as u32,
+            room.height * TILE_SIZE as u32,
+        ))
+        .unwrap();
+}
+
+/// Draws the visible layers of a room
+///
+/// Each tile layer's tiles that don't animate are drawn from a cached texture of the whole layer where possible, with
+/// the animated ones drawn over the top every frame.
+fn draw_room(
+    canvas: &mut Canvas<Window>,
+    texture_cache: &mut TextureCache,
+    level_id: i32,
+    room: &level::Room,
+    origin: Point,
+    camera: Rect,
+    options: DrawOptions,
+) {
+    let DrawOptions {
+        layers,
+        animations,
+        tick,
+        alpha,
+        ..
+    } = options;
+    let size = (
+        room.width * TILE_SIZE as u32,
+        room.height * TILE_SIZE as u32,
+    );
+    let is_animated = |tile: &Tile| animations.is_animated(&room.tileset, tile.id);
+    for (layer, tiles) in [
+        (Layer::Bottom, &room.bottom_tiles),
+        (Layer::Middle, &room.middle_tiles),
+        (Layer::Top, &room.top_tiles),
+    ] {
+        let Some(layer_alpha) = layers.alpha(layer) else {
+            continue;
+        };
+        if tiles.is_empty() {
+            continue;
+        }
+        let Some((tileset, frames, rooms)) = texture_cache.load(&room.tileset) else {
+            break;
+        };
+        let layer_alpha = scale_alpha(layer_alpha, alpha);
+
+        let cached = rooms.layer(
+            canvas,
+            (level_id, room.id, layer),
+            &room.tileset,
+            size,
+            |target| {
+                // the room fills the whole texture, so it's drawn as if the camera's top left were the room's
+                let room_camera = Rect::new(0, 0, size.0, size.1);
+                for tile in tiles.iter().filter(|tile| !is_animated(tile)) {
+                    draw_tile(
+                        target,
+                        tile,
+                        tile.id,
+                        tileset,
+                        Point::new(0, 0),
+                        room_camera,
+                        frames,
+                    );
+                }
+            },
+        );
+        let drawn_cached = match cached {
+            Some(texture) => {
+                let rect = Rect::new(origin.x - camera.x, origin.y - camera.y, size.0, size.1);
+                texture.set_alpha_mod(layer_alpha);
+                canvas.copy(texture, None, rect).unwrap();
+                true
+            }
+            None => false,
+        };
+
+        tileset.set_alpha_mod(layer_alpha);
+        for tile in tiles {
+            if drawn_cached && !is_animated(tile) {
+                continue;
+            }
+            let frame = animations.tile_frame(&room.tileset, tile.id, tick);
+            draw_tile(canvas, tile, frame, tileset, origin, camera, frames);
+        }
+        tileset.set_alpha_mod(255);
+    }
+
+    draw_room_overlays(
+        canvas,
+        &mut texture_cache.rooms,
+        level_id,
+        room,
+        origin,
+        camera,
+        options,
+    );
+}
+
+/// Draws the visible layers of a room that go over its tiles
+fn draw_room_overlays(
+    canvas: &mut Canvas<Window>,
+    rooms: &mut RoomCache,
+    level_id: i32,
+    room: &level::Room,
+    origin: Point,
+    camera: Rect,
+    options: DrawOptions,
+) {
+    let DrawOptions {
+        layers,
+        collisions,
+        alpha,
+        ..
+    } = options;
+    if let Some(layer_alpha) = layers.alpha(Layer::Collision) {
+        draw_collision(
+            canvas,
+            rooms.collision(level_id, room, collisions),
+            origin,
+            camera,
+            scale_alpha(layer_alpha, alpha),
+        );
+    }
+
+    if let Some(layer_alpha) = layers.alpha(Layer::Objects) {
+        draw_objects(
+            canvas,
+            room,
+            origin,
+            camera,
+            scale_alpha(layer_alpha, alpha),
+        );
+    }
+}
+
+fn scale_alpha(a: u8, b: u8) -> u8 {
+    (a as u32 * b as u32 / 255) as u8
+}
+
+/// Letter and colour an object's marker is drawn with
+fn object_style(category: Category) -> (&'static str, Color) {
+    match category {
+        Category::None => ("?", Color::RGB(160, 160, 160)),
+        Category::Event(_) => ("E", Color::RGB(255, 200, 0)),
+        Category::Enemy(_) => ("M", Color::RGB(255, 60, 60)),
+        Category::Item(_) => ("I", Color::RGB(60, 220, 90)),
+    }
+}
+
+/// Colour a cell is tinted with for how it collides, or `None` for empty cells
+fn collision_color(collision: Collision) -> Option<Color> {
+    match collision {
+        Collision::Empty => None,
+        Collision::Solid => Some(Color::RGB(60, 120, 255)),
+        Collision::OneWay => Some(Color::RGB(60, 220, 220)),
+        Collision::Slope => Some(Color::RGB(200, 80, 255)),
+        Collision::Hazard => Some(Color::RGB(255, 40, 40)),
+        Collision::Breakable => Some(Color::RGB(255, 160, 40)),
+    }
+}
+
+/// Tints every cell of a room by how it collides
+fn draw_collision(
+    canvas: &mut Canvas<Window>,
+    grid: &CollisionGrid,
+    origin: Point,
+    camera: Rect,
+    alpha: u8,
+) {
+    canvas.set_blend_mode(BlendMode::Blend);
+    for &collision in &Collision::ALL {
+        let Some(color) = collision_color(collision) else {
+            continue;
+        };
+        let rects: Vec<Rect> = (0..grid.height() as i32)
+            .flat_map(|y| (0..grid.width() as i32).map(move |x| (x, y)))
+            .filter(|&(x, y)| grid.get(x, y) == collision)
+            .map(|(x, y)| {
+                Rect::new(
+                    x * TILE_SIZE - camera.x + origin.x,
+                    y * TILE_SIZE - camera.y + origin.y,
+                    TILE_SIZE as u32,
+                    TILE_SIZE as u32,
+                )
+            })
+            .collect();
+        if !rects.is_empty() {
+            canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, alpha / 2));
+            canvas.fill_rects(&rects).unwrap();
+        }
+    }
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+/// Marks every object in a room with a box and a letter for its category
+fn draw_objects(
+    canvas: &mut Canvas<Window>,
+    room: &level::Room,
+    origin: Point,
+    camera: Rect,
+    alpha: u8,
+) {
+    canvas.set_blend_mode(BlendMode::Blend);
+    for object in &room.object_tiles {
+        let rect = Rect::new(
+            object.x * TILE_SIZE - camera.x + origin.x,
+            object.y * TILE_SIZE - camera.y + origin.y,
+            TILE_SIZE as u32,
+            TILE_SIZE as u32,
+        );
+        let (letter, color) = object_style(object.category);
+        canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, alpha / 3));
+        canvas.fill_rect(rect).unwrap();
+        canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, alpha));
+        canvas.draw_rect(rect).unwrap();
+        draw_text(
+            canvas,
+            letter,
+            rect.x() + (TILE_SIZE - font::GLYPH_WIDTH) / 2,
+            rect.y() + (TILE_SIZE - font::GLYPH_HEIGHT) / 2,
+            1,
+            Color::RGBA(255, 255, 255, alpha),
+        );
+    }
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+/// Highlights cells that differ between two rooms
+fn draw_diff(canvas: &mut Canvas<Window>, cells: &[Point], origin: Point, camera: Rect) {
+    let rects: Vec<Rect> = cells
+        .iter()
+        .map(|cell| {
+            Rect::new(
+                cell.x * TILE_SIZE - camera.x + origin.x,
+                cell.y * TILE_SIZE - camera.y + origin.y,
+                TILE_SIZE as u32,
+                TILE_SIZE as u32,
+            )
+        })
+        .collect();
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(255, 0, 0, 96));
+    canvas.fill_rects(&rects).unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+fn draw_text(canvas: &mut Canvas<Window>, text: &str, x: i32, y: i32, scale: i32, color: Color) {
+    let mut rects = Vec::new();
+    font::for_each_pixel(text, |px, py| {
+        rects.push(Rect::new(
+            x + px * scale,
+            y + py * scale,
+            scale as u32,
+            scale as u32,
+        ));
+    });
+    canvas.set_draw_color(color);
+    canvas.fill_rects(&rects).unwrap();
+}
+
+const TEXT_BOX_SCALE: i32 = 2;
+const TEXT_BOX_LINE_HEIGHT: i32 = (font::GLYPH_HEIGHT + 3) * TEXT_BOX_SCALE;
+const TEXT_BOX_PADDING: i32 = 8;
+
+/// Draws lines of text in a dark box along the top of the window
+fn draw_text_box(canvas: &mut Canvas<Window>, lines: &[(String, Color)]) {
+    draw_text_box_at(canvas, lines, TEXT_BOX_PADDING);
+}
+
+/// Draws lines of text in a dark box along the bottom of the window
+fn draw_text_box_bottom(canvas: &mut Canvas<Window>, lines: &[(String, Color)]) {
+    let height = TEXT_BOX_LINE_HEIGHT * lines.len() as i32 + TEXT_BOX_PADDING * 2;
+    let y = canvas.viewport().height() as i32 - height - TEXT_BOX_PADDING;
+    draw_text_box_at(canvas, lines, y);
+}
+
+fn draw_text_box_at(canvas: &mut Canvas<Window>, lines: &[(String, Color)], y: i32) {
+    let width = canvas
+        .viewport()
+        .width()
+        .saturating_sub(TEXT_BOX_PADDING as u32 * 2);
+    let height = (TEXT_BOX_LINE_HEIGHT * lines.len() as i32 + TEXT_BOX_PADDING * 2) as u32;
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 208));
+    canvas
+        .fill_rect(Rect::new(TEXT_BOX_PADDING, y, width, height))
+        .unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+
+    for (i, (text, color)) in lines.iter().enumerate() {
+        let line_y = y + TEXT_BOX_PADDING + TEXT_BOX_LINE_HEIGHT * i as i32;
+        draw_text(
+            canvas,
+            text,
+            TEXT_BOX_PADDING * 2,
+            line_y,
+            TEXT_BOX_SCALE,
+            *color,
+        );
+    }
+}
+
+fn draw_grid(canvas: &mut Canvas<Window>, camera: Rect, alpha: u8) {
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(32, 32, 32, alpha));
+    let mut x = camera.x / TILE_SIZE * TILE_SIZE - camera.x;
+    if x < 0 {
+        x += TILE_SIZE;
+    }
+    while x < camera.w {
+        canvas.draw_line((x, 0), (x, camera.h)).unwrap();
+        x += TILE_SIZE;
+    }
+
+    let mut y = camera.y / TILE_SIZE * TILE_SIZE - camera.y;
+    if y < 0 {
+        y += TILE_SIZE;
+    }
+    while y < camera.h {
+        canvas.draw_line((0, y), (camera.w, y)).unwrap();
+        y += TILE_SIZE;
+    }
+
+    canvas.set_draw_color(Color::RGBA(0, 64, 0, alpha));
+    let mut x = camera.x / (TILE_SIZE * ROOM_WIDTH) * (TILE_SIZE * ROOM_WIDTH) - camera.x;
+    if x < 0 {
+        x += TILE_SIZE * ROOM_WIDTH;
+    }
+    while x < camera.w {
+        canvas.draw_line((x, 0), (x, camera.h)).unwrap();
+        x += TILE_SIZE * ROOM_WIDTH;
+    }
+
+    let mut y = camera.y / (TILE_SIZE * ROOM_HEIGHT) * (TILE_SIZE * ROOM_HEIGHT) - camera.y;
+    if y < 0 {
+        y += TILE_SIZE * ROOM_HEIGHT;
+    }
+    while y < camera.h {
+        canvas.draw_line((0, y), (camera.w, y)).unwrap();
+        y += TILE_SIZE * ROOM_HEIGHT;
+    }
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+/// Draws a tile showing the given frame of its tileset
+fn draw_tile(
+    canvas: &mut Canvas<Window>,
+    tile: &Tile,
+    frame: i32,
+    tileset: &Texture,
+    room_origin: Point,
+    camera: Rect,
+    frames: &[Rect],
+) {
+    if tile.id >= 512 {
+        // TODO: tiles have special behaviour here
+        return;
+    }
+    let Some(&tileset_rect) = frames.get(frame as usize) else {
+        return;
+    };
+
+    let screen_rect = Rect::new(
+        tile.x * TILE_SIZE - camera.x + room_origin.x,
+        tile.y * TILE_SIZE - camera.y + room_origin.y,
+        TILE_SIZE as u32,
+        TILE_SIZE as u32,
+    );
+
+    canvas
+        .copy_ex(
+            tileset,
+            tileset_rect,
+            screen_rect,
+            0.0,
+            None,
+            tile.flip_x,
+            tile.flip_y,
+        )
+        .unwrap();
+}
+
+/// Content path and frames of every atlas, keyed by file name
+fn atlas_frames(texture_database: TextureDatabase) -> HashMap<String, (String, Vec<Rect>)> {
+    texture_database
+        .atlases
+        .into_iter()
+        .map(|atlas| {
+            let frames = atlas.frame_rects().into_iter().map(Rect::from).collect();
+            (atlas.file_name, (atlas.content_path, frames))
+        })
+        .collect()
+}
+
+/// Atlas textures, loaded the first time they're drawn and unloaded when they haven't been drawn in a while
+struct TextureCache {
+    textures: TextureLru<String>,
+    texture_creator: TextureCreator<WindowContext>,
+    /// Content path and frames of every atlas, keyed by file name
+    atlases: HashMap<String, (String, Vec<Rect>)>,
+    /// Atlases that couldn't be loaded, so they aren't tried again every frame
+    failed: HashSet<String>,
+    /// Room layers drawn from the atlases
+    rooms: RoomCache,
+}
+
+impl TextureCache {
+    fn new(canvas: &Canvas<Window>, texture_database: TextureDatabase) -> TextureCache {
+        TextureCache {
+            textures: TextureLru::new(TEXTURE_BUDGET),
+            texture_creator: canvas.texture_creator(),
+            atlases: atlas_frames(texture_database),
+            failed: HashSet::new(),
+            rooms: RoomCache::new(canvas),
+        }
+    }
+
+    /// The texture and frames of an atlas, loading the texture if it isn't already, along with the room layers cache
+    /// to draw it through
+    fn load(&mut self, name: &str) -> Option<(&mut Texture, &[Rect], &mut RoomCache)> {
+        let (content_path, frames) = self.atlases.get(name)?;
+        if !self.textures.contains(name) {
+            if self.failed.contains(name) {
+                return None;
+            }
+            let path = format!("./Content/{content_path}.png");
+            let texture = match self.texture_creator.load_texture(&path) {
+                Ok(texture) => texture,
+                Err(err) => {
+                    eprintln!("couldn't load {path}: {err}");
+                    self.failed.insert(name.to_owned());
+                    return None;
+                }
+            };
+            self.textures.insert(name.to_owned(), texture, ());
+        }
+
+        let texture = self.textures.get_mut(name).unwrap();
+        Some((texture, frames, &mut self.rooms))
+    }
+
+    /// Unloads the texture loaded from a PNG, so it's loaded again the next time it's drawn
+    fn reload(&mut self, path: &Path) {
+        let names: Vec<String> = self
+            .atlases
+            .iter()
+            .filter(|(_, (content_path, _))| {
+                Path::new(&format!("./Content/{content_path}.png")) == path
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            self.textures.remove(&name);
+            self.failed.remove(&name);
+            self.rooms.forget_tileset(&name);
+        }
+    }
+
+    /// Swaps in a new texture database, unloading every texture
+    fn set_database(&mut self, texture_database: TextureDatabase) {
+        self.textures.clear();
+        self.failed.clear();
+        self.rooms.clear();
+        self.atlases = atlas_frames(texture_database);
+    }
+
+    /// Unloads the least recently used textures until they fit in the budget, keeping any drawn this frame
+    fn end_frame(&mut self) {
+        self.textures.end_frame();
+        self.rooms.end_frame();
+    }
+}
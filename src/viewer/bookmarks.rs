@@ -0,0 +1,53 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::layers::LayerSettings;
+
+/// A saved view of the map
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// Function key the bookmark is recalled with
+    pub slot: u32,
+    pub name: String,
+    /// Camera position in pixels
+    pub x: i32,
+    pub y: i32,
+    pub zoom: f32,
+    /// Which eras are shown, as written by `EraView::key`
+    pub era_view: String,
+    /// Visibility and opacity of each layer, keyed by `Layer::key`, which older bookmarks don't have
+    #[serde(default)]
+    pub layers: BTreeMap<String, LayerSettings>,
+}
+
+/// Every saved bookmark, as stored in the bookmarks file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    #[serde(rename = "bookmark", default)]
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Bookmarks> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, slot: u32) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|bookmark| bookmark.slot == slot)
+    }
+
+    /// Adds a bookmark, replacing any other bookmark in the same slot
+    pub fn set(&mut self, bookmark: Bookmark) {
+        self.bookmarks.retain(|other| other.slot != bookmark.slot);
+        self.bookmarks.push(bookmark);
+        self.bookmarks.sort_by_key(|bookmark| bookmark.slot);
+    }
+}
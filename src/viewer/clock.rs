@@ -0,0 +1,49 @@
+use std::time::Instant;
+use timespinner_map::animation::TICKS_PER_SECOND;
+
+/// Game time for animations, which can be paused and stepped through a tick at a time
+#[derive(Clone, Debug)]
+pub struct Clock {
+    /// Game ticks since the viewer started, including partial ticks
+    ticks: f64,
+    paused: bool,
+    last_update: Instant,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {
+            ticks: 0.0,
+            paused: false,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Moves time along by however long it's been since the last update, unless paused
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        if !self.paused {
+            let elapsed = now - self.last_update;
+            self.ticks += elapsed.as_secs_f64() * TICKS_PER_SECOND as f64;
+        }
+        self.last_update = now;
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.ticks as u64
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Pauses and moves on to the start of the next tick
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.ticks = self.ticks.floor() + 1.0;
+    }
+}
@@ -0,0 +1,94 @@
+use sdl2::rect::{Point, Rect};
+use timespinner_map::TILE_SIZE;
+use timespinner_map::level::{Level, Room};
+use timespinner_map::world::World;
+
+use super::{LABEL_SCALE, font};
+
+/// Gap between rooms in the gallery, and between the map and the gallery, in pixels
+const GAP: i32 = TILE_SIZE * 8;
+/// Most rooms in a row of the gallery
+const ROW_LENGTH: usize = 8;
+
+/// A room drawn in the gallery instead of where the minimap puts it
+#[derive(Clone, Debug)]
+pub struct GalleryRoom {
+    pub level_id: i32,
+    pub room_id: i32,
+    /// Where the room is drawn, in pixels
+    pub bounds: Rect,
+}
+
+/// A labelled group of rooms in the gallery
+#[derive(Clone, Debug)]
+pub struct GallerySection {
+    pub label: String,
+    pub label_position: Point,
+    pub rooms: Vec<GalleryRoom>,
+}
+
+/// Lays out rooms that aren't on the minimap in rows below the map
+///
+/// Each level with rooms the minimap leaves out gets a section, followed by a section for each level that isn't on the
+/// minimap at all.
+pub fn layout(world: &World) -> Vec<GallerySection> {
+    let mut groups: Vec<(String, &Level, Vec<&Room>)> = world
+        .unplaced_rooms()
+        .into_iter()
+        .map(|(level, rooms)| {
+            let label = match rooms.len() {
+                1 => format!(
+                    "Level {}: {} - 1 room not on the minimap",
+                    level.id, level.name
+                ),
+                count => format!(
+                    "Level {}: {} - {count} rooms not on the minimap",
+                    level.id, level.name
+                ),
+            };
+            (label, level, rooms)
+        })
+        .collect();
+    for level in world.unreferenced_levels() {
+        let label = format!("Level {}: {} - not on the minimap", level.id, level.name);
+        groups.push((label, level, level.rooms.iter().collect()));
+    }
+
+    let (left, mut y) = match world.bounds() {
+        Some(bounds) => (bounds.x(), bounds.bottom() + GAP),
+        None => (0, 0),
+    };
+
+    let mut sections = Vec::new();
+    for (label, level, level_rooms) in groups {
+        let label_position = Point::new(left, y);
+        y += (font::GLYPH_HEIGHT + 2) * LABEL_SCALE;
+
+        let mut rooms = Vec::new();
+        for row in level_rooms.chunks(ROW_LENGTH) {
+            let mut x = left;
+            let mut row_height = 0;
+            for room in row {
+                let (width, height) = (
+                    room.width * TILE_SIZE as u32,
+                    room.height * TILE_SIZE as u32,
+                );
+                rooms.push(GalleryRoom {
+                    level_id: level.id,
+                    room_id: room.id,
+                    bounds: Rect::new(x, y, width, height),
+                });
+                x += width as i32 + GAP;
+                row_height = row_height.max(height as i32);
+            }
+            y += row_height + GAP;
+        }
+
+        sections.push(GallerySection {
+            label,
+            label_position,
+            rooms,
+        });
+    }
+    sections
+}
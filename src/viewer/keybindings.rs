@@ -0,0 +1,176 @@
+use anyhow::{Result, anyhow};
+use sdl2::keyboard::Keycode;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Something the viewer can do from the keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    PreviousEra,
+    NextEra,
+    /// Moves the camera back to (0, 0)
+    Home,
+    CycleEraView,
+    CycleCompare,
+    /// Opens the jump-to-room search box
+    Search,
+    ZoomIn,
+    ZoomOut,
+    /// Pauses or resumes tile animations
+    TogglePause,
+    /// Pauses tile animations and moves them on by a game tick
+    StepAnimation,
+    /// Picks the next layer to toggle or dim
+    NextLayer,
+    ToggleLayer,
+    LayerOpacityUp,
+    LayerOpacityDown,
+    /// Recalls a bookmark, holding shift saves the current view to it instead
+    Bookmark(u32),
+    /// Centers the camera on an area, holding shift adds 10 to the area ID
+    JumpToArea(i32),
+}
+
+impl Action {
+    /// Parses the name an action has in the keybindings file, such as `pan_left` or `jump_to_area_3`
+    fn from_name(name: &str) -> Option<Action> {
+        Some(match name {
+            "pan_left" => Action::PanLeft,
+            "pan_right" => Action::PanRight,
+            "pan_up" => Action::PanUp,
+            "pan_down" => Action::PanDown,
+            "previous_era" => Action::PreviousEra,
+            "next_era" => Action::NextEra,
+            "home" => Action::Home,
+            "cycle_era_view" => Action::CycleEraView,
+            "cycle_compare" => Action::CycleCompare,
+            "search" => Action::Search,
+            "zoom_in" => Action::ZoomIn,
+            "zoom_out" => Action::ZoomOut,
+            "toggle_pause" => Action::TogglePause,
+            "step_animation" => Action::StepAnimation,
+            "next_layer" => Action::NextLayer,
+            "toggle_layer" => Action::ToggleLayer,
+            "layer_opacity_up" => Action::LayerOpacityUp,
+            "layer_opacity_down" => Action::LayerOpacityDown,
+            _ => {
+                if let Some(slot) = name.strip_prefix("bookmark_") {
+                    Action::Bookmark(slot.parse().ok()?)
+                } else {
+                    Action::JumpToArea(name.strip_prefix("jump_to_area_")?.parse().ok()?)
+                }
+            }
+        })
+    }
+}
+
+/// Which key does what
+#[derive(Clone, Debug)]
+pub struct Keybindings {
+    bindings: HashMap<Keycode, Action>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Keybindings {
+        let mut bindings = HashMap::from([
+            (Keycode::LEFT, Action::PanLeft),
+            (Keycode::A, Action::PanLeft),
+            (Keycode::RIGHT, Action::PanRight),
+            (Keycode::D, Action::PanRight),
+            (Keycode::UP, Action::PanUp),
+            (Keycode::W, Action::PanUp),
+            (Keycode::DOWN, Action::PanDown),
+            (Keycode::S, Action::PanDown),
+            (Keycode::PAGEUP, Action::PreviousEra),
+            (Keycode::PAGEDOWN, Action::NextEra),
+            (Keycode::HOME, Action::Home),
+            (Keycode::E, Action::CycleEraView),
+            (Keycode::C, Action::CycleCompare),
+            (Keycode::SLASH, Action::Search),
+            (Keycode::F, Action::Search),
+            (Keycode::EQUALS, Action::ZoomIn),
+            (Keycode::KP_PLUS, Action::ZoomIn),
+            (Keycode::MINUS, Action::ZoomOut),
+            (Keycode::KP_MINUS, Action::ZoomOut),
+            (Keycode::P, Action::TogglePause),
+            (Keycode::PERIOD, Action::StepAnimation),
+            (Keycode::L, Action::NextLayer),
+            (Keycode::V, Action::ToggleLayer),
+            (Keycode::RIGHTBRACKET, Action::LayerOpacityUp),
+            (Keycode::LEFTBRACKET, Action::LayerOpacityDown),
+        ]);
+        let number_keys = [
+            Keycode::NUM_0,
+            Keycode::NUM_1,
+            Keycode::NUM_2,
+            Keycode::NUM_3,
+            Keycode::NUM_4,
+            Keycode::NUM_5,
+            Keycode::NUM_6,
+            Keycode::NUM_7,
+            Keycode::NUM_8,
+            Keycode::NUM_9,
+        ];
+        for (area, key) in number_keys.into_iter().enumerate() {
+            bindings.insert(key, Action::JumpToArea(area as i32));
+        }
+        let function_keys = [
+            Keycode::F1,
+            Keycode::F2,
+            Keycode::F3,
+            Keycode::F4,
+            Keycode::F5,
+            Keycode::F6,
+            Keycode::F7,
+            Keycode::F8,
+            Keycode::F9,
+            Keycode::F10,
+            Keycode::F11,
+            Keycode::F12,
+        ];
+        for (slot, key) in function_keys.into_iter().enumerate() {
+            bindings.insert(key, Action::Bookmark(slot as u32 + 1));
+        }
+        Keybindings { bindings }
+    }
+}
+
+impl Keybindings {
+    /// Loads keybindings from a TOML file on top of the defaults
+    ///
+    /// The file maps action names to the names SDL gives keys, e.g. `pan_left = ["Left", "A"]`. Any action listed
+    /// replaces all of its default keys.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Keybindings> {
+        let file: HashMap<String, Vec<String>> = toml::from_str(&fs::read_to_string(path)?)?;
+
+        let mut keybindings = Keybindings::default();
+        for (name, keys) in file {
+            let action =
+                Action::from_name(&name).ok_or_else(|| anyhow!("unknown action {name}"))?;
+            keybindings.bindings.retain(|_, bound| *bound != action);
+            for key in keys {
+                let keycode =
+                    Keycode::from_name(&key).ok_or_else(|| anyhow!("unknown key {key}"))?;
+                keybindings.bindings.insert(keycode, action);
+            }
+        }
+        Ok(keybindings)
+    }
+
+    pub fn action(&self, keycode: Keycode) -> Option<Action> {
+        self.bindings.get(&keycode).copied()
+    }
+
+    /// Every key bound to an action
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = Keycode> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| **bound == action)
+            .map(|(key, _)| *key)
+    }
+}
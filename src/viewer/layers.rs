@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Something the viewer draws that can be hidden or dimmed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Each room's background wipe colour
+    Backdrop,
+    Bottom,
+    Middle,
+    Top,
+    /// Tint over each cell showing how it collides
+    Collision,
+    /// Markers for events, enemies and items
+    Objects,
+    Grid,
+    /// Era labels
+    Labels,
+    /// The cells the minimap covers for each room
+    MinimapBlocks,
+}
+
+impl Layer {
+    /// Every layer, from the bottom of the stack to the top
+    pub const ALL: [Layer; 9] = [
+        Layer::Grid,
+        Layer::Backdrop,
+        Layer::MinimapBlocks,
+        Layer::Bottom,
+        Layer::Middle,
+        Layer::Top,
+        Layer::Collision,
+        Layer::Objects,
+        Layer::Labels,
+    ];
+
+    /// Name of the layer in the bookmarks file
+    pub fn key(&self) -> &'static str {
+        match self {
+            Layer::Backdrop => "backdrop",
+            Layer::Bottom => "bottom",
+            Layer::Middle => "middle",
+            Layer::Top => "top",
+            Layer::Collision => "collision",
+            Layer::Objects => "objects",
+            Layer::Grid => "grid",
+            Layer::Labels => "labels",
+            Layer::MinimapBlocks => "minimap_blocks",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Layer> {
+        Layer::ALL.into_iter().find(|layer| layer.key() == key)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layer::Backdrop => "Backdrop",
+            Layer::Bottom => "Bottom tiles",
+            Layer::Middle => "Middle tiles",
+            Layer::Top => "Top tiles",
+            Layer::Collision => "Collision",
+            Layer::Objects => "Objects",
+            Layer::Grid => "Grid",
+            Layer::Labels => "Labels",
+            Layer::MinimapBlocks => "Minimap blocks",
+        }
+    }
+}
+
+/// How much opacity changes with each key press
+const OPACITY_STEP: u8 = 32;
+
+/// Whether a layer is shown and how see-through it is
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct LayerSettings {
+    pub visible: bool,
+    pub opacity: u8,
+}
+
+/// Visibility and opacity of every layer
+#[derive(Clone, Debug)]
+pub struct Layers {
+    settings: [LayerSettings; Layer::ALL.len()],
+    /// Index into [`Layer::ALL`] of the layer the keyboard changes
+    selected: usize,
+}
+
+impl Default for Layers {
+    fn default() -> Layers {
+        let settings = Layer::ALL.map(|layer| LayerSettings {
+            visible: !matches!(layer, Layer::MinimapBlocks | Layer::Collision),
+            opacity: 255,
+        });
+        Layers {
+            settings,
+            selected: 0,
+        }
+    }
+}
+
+impl Layers {
+    fn index(layer: Layer) -> usize {
+        Layer::ALL.iter().position(|&other| other == layer).unwrap()
+    }
+
+    /// Alpha to draw a layer with, or `None` if it shouldn't be drawn at all
+    pub fn alpha(&self, layer: Layer) -> Option<u8> {
+        let settings = self.settings[Layers::index(layer)];
+        (settings.visible && settings.opacity > 0).then_some(settings.opacity)
+    }
+
+    pub fn selected(&self) -> Layer {
+        Layer::ALL[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % Layer::ALL.len();
+    }
+
+    pub fn toggle_selected(&mut self) {
+        let settings = &mut self.settings[self.selected];
+        settings.visible = !settings.visible;
+    }
+
+    /// Changes the opacity of the selected layer by a step up or down
+    pub fn change_opacity(&mut self, up: bool) {
+        let settings = &mut self.settings[self.selected];
+        settings.opacity = if up {
+            settings.opacity.saturating_add(OPACITY_STEP)
+        } else {
+            settings.opacity.saturating_sub(OPACITY_STEP)
+        };
+    }
+
+    /// Settings of every layer keyed by [`Layer::key`], for saving in a bookmark
+    pub fn save(&self) -> BTreeMap<String, LayerSettings> {
+        Layer::ALL
+            .iter()
+            .zip(&self.settings)
+            .map(|(layer, settings)| (layer.key().to_owned(), *settings))
+            .collect()
+    }
+
+    /// Applies settings saved by [`Layers::save`], leaving layers they don't mention alone
+    ///
+    /// Returns the keys that aren't the name of any layer.
+    pub fn restore(&mut self, saved: &BTreeMap<String, LayerSettings>) -> Vec<String> {
+        let mut unknown = Vec::new();
+        for (key, settings) in saved {
+            match Layer::from_key(key) {
+                Some(layer) => self.settings[Layers::index(layer)] = *settings,
+                None => unknown.push(key.clone()),
+            }
+        }
+        unknown
+    }
+
+    /// A line describing each layer, from the top of the stack to the bottom
+    pub fn describe(&self) -> Vec<(Layer, String)> {
+        Layer::ALL
+            .iter()
+            .zip(&self.settings)
+            .rev()
+            .map(|(&layer, settings)| {
+                let visible = if settings.visible { "on " } else { "off" };
+                let percent = settings.opacity as u32 * 100 / 255;
+                (layer, format!("{visible} {percent:>3}% {}", layer.name()))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use sdl2::EventPump;
+use sdl2::event::Event;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::exit;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use timespinner_map::compare::pair_rooms;
+use timespinner_map::era::Era;
+use timespinner_map::texture_atlas::TextureDatabase;
+use timespinner_map::world::World;
+
+use super::{RoomKey, draw_text, font};
+
+/// Everything the viewer needs loaded before it can show the map
+pub struct Loaded {
+    pub world: World,
+    pub texture_database: TextureDatabase,
+    /// The room in the other era each room corresponds to
+    pub counterparts: HashMap<RoomKey, RoomKey>,
+}
+
+/// How far along loading is, shared with the thread doing the loading
+#[derive(Debug, Default)]
+struct Progress {
+    stage: Mutex<&'static str>,
+    done: AtomicUsize,
+    /// How many steps the stage has, or 0 if it's a single step
+    total: AtomicUsize,
+}
+
+impl Progress {
+    fn start(&self, stage: &'static str) {
+        *self.stage.lock().unwrap() = stage;
+        self.set(0, 0);
+    }
+
+    fn set(&self, done: usize, total: usize) {
+        self.done.store(done, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+}
+
+/// Pairs up rooms in the present with rooms in the past, both ways round
+pub fn find_counterparts(world: &World) -> HashMap<RoomKey, RoomKey> {
+    eprintln!("pairing rooms across eras...");
+    let mut counterparts = HashMap::new();
+    for pair in pair_rooms(world, Era::Present, Era::Past) {
+        let a = (pair.a.area.id, pair.a.room.id);
+        let b = (pair.b.area.id, pair.b.room.id);
+        counterparts.insert(a, b);
+        counterparts.insert(b, a);
+    }
+    eprintln!("done!");
+    counterparts
+}
+
+fn load_all(timespinner_path: &Path, progress: &Progress) -> Result<Loaded> {
+    progress.start("Loading levels");
+    eprintln!("loading levels...");
+    let mut world =
+        World::load_with_progress(timespinner_path, |done, total| progress.set(done, total))?;
+    eprintln!("done!");
+    crate::print_warnings(&world.warnings());
+    world.set_era_table(&super::load_era_table());
+
+    progress.start("Loading texture database");
+    eprintln!("loading texture database...");
+    let texture_database = TextureDatabase::load(timespinner_path)?;
+    eprintln!("done!");
+
+    progress.start("Pairing rooms across eras");
+    let counterparts = find_counterparts(&world);
+
+    Ok(Loaded {
+        world,
+        texture_database,
+        counterparts,
+    })
+}
+
+/// Loads everything on another thread, showing a loading screen until it's done
+pub fn load(
+    canvas: &mut Canvas<Window>,
+    event_pump: &mut EventPump,
+    timespinner_path: &Path,
+) -> anyhow::Result<Loaded> {
+    let progress = Progress::default();
+    thread::scope(|scope| {
+        let handle = scope.spawn(|| load_all(timespinner_path, &progress));
+        while !handle.is_finished() {
+            for event in event_pump.poll_iter() {
+                if let Event::Quit { .. } = event {
+                    exit(0);
+                }
+            }
+            draw_loading_screen(canvas, &progress);
+        }
+        handle.join().unwrap()
+    })
+}
+
+fn draw_loading_screen(canvas: &mut Canvas<Window>, progress: &Progress) {
+    const SCALE: i32 = 2;
+    const BAR_HEIGHT: u32 = 16;
+
+    let stage = *progress.stage.lock().unwrap();
+    let done = progress.done.load(Ordering::Relaxed);
+    let total = progress.total.load(Ordering::Relaxed);
+    let label = if total > 0 {
+        format!("{stage}... {done}/{total}")
+    } else {
+        format!("{stage}...")
+    };
+
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+
+    let viewport = canvas.viewport();
+    let bar_width = viewport.width() * 3 / 4;
+    let bar = Rect::new(
+        (viewport.width() - bar_width) as i32 / 2,
+        viewport.height() as i32 / 2,
+        bar_width,
+        BAR_HEIGHT,
+    );
+    draw_text(
+        canvas,
+        &label,
+        bar.x(),
+        bar.y() - (font::GLYPH_HEIGHT + 4) * SCALE,
+        SCALE,
+        Color::WHITE,
+    );
+
+    if let Some(filled) = (bar_width as usize * done).checked_div(total) {
+        let filled = filled as u32;
+        if filled > 0 {
+            canvas.set_draw_color(Color::RGB(0, 160, 0));
+            canvas
+                .fill_rect(Rect::new(bar.x(), bar.y(), filled, BAR_HEIGHT))
+                .unwrap();
+        }
+    }
+    canvas.set_draw_color(Color::WHITE);
+    canvas.draw_rect(bar).unwrap();
+
+    canvas.present();
+}
@@ -0,0 +1,122 @@
+use sdl2::render::Texture;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Most memory each cache's textures can take up before the least recently drawn ones are destroyed, in bytes
+pub const TEXTURE_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Frees a texture now instead of when the renderer it came from is dropped
+fn destroy_texture(texture: Texture) {
+    // SAFETY: textures are only destroyed while the viewer is running, when the canvas they came from is still alive
+    unsafe { texture.destroy() };
+}
+
+struct Entry<T> {
+    texture: Texture,
+    /// Anything else the cache needs to know about the texture
+    data: T,
+    /// Size of the texture in bytes
+    size: usize,
+    /// Frame the texture was last drawn on
+    last_used: u64,
+}
+
+/// Textures that are destroyed, least recently drawn first, once they take up more memory than a budget
+pub struct TextureLru<K, T = ()> {
+    entries: HashMap<K, Entry<T>>,
+    /// Most memory the textures can take up at the end of a frame, in bytes
+    budget: usize,
+    /// Bytes taken up by every texture
+    used: usize,
+    frame: u64,
+}
+
+impl<K: Clone + Eq + Hash, T> TextureLru<K, T> {
+    pub fn new(budget: usize) -> TextureLru<K, T> {
+        TextureLru {
+            entries: HashMap::new(),
+            budget,
+            used: 0,
+            frame: 0,
+        }
+    }
+
+    pub fn contains<Q: Eq + Hash + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Adds a texture, destroying any other texture with the same key
+    pub fn insert(&mut self, key: K, texture: Texture, data: T) {
+        self.remove(&key);
+        let query = texture.query();
+        let size = query.width as usize * query.height as usize * 4;
+        self.used += size;
+        self.entries.insert(
+            key,
+            Entry {
+                texture,
+                data,
+                size,
+                last_used: self.frame,
+            },
+        );
+    }
+
+    /// A texture, marked as drawn this frame
+    pub fn get_mut<Q: Eq + Hash + ?Sized>(&mut self, key: &Q) -> Option<&mut Texture>
+    where
+        K: Borrow<Q>,
+    {
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.frame;
+        Some(&mut entry.texture)
+    }
+
+    pub fn remove<Q: Eq + Hash + ?Sized>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+    {
+        if let Some(entry) = self.entries.remove(key) {
+            self.used -= entry.size;
+            destroy_texture(entry.texture);
+        }
+    }
+
+    /// Destroys every texture `predicate` picks
+    pub fn remove_where(&mut self, mut predicate: impl FnMut(&K, &T) -> bool) {
+        let keys: Vec<K> = self
+            .entries
+            .iter()
+            .filter(|(key, entry)| predicate(key, &entry.data))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.remove_where(|_, _| true);
+    }
+
+    /// Destroys the least recently drawn textures until they fit in the budget, keeping any drawn this frame
+    pub fn end_frame(&mut self) {
+        while self.used > self.budget {
+            let Some(key) = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.last_used < self.frame)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&key);
+        }
+        self.frame += 1;
+    }
+}
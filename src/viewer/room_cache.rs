@@ -0,0 +1,123 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use std::collections::HashMap;
+use timespinner_map::collision::{CollisionGrid, CollisionTable};
+use timespinner_map::level::Room;
+
+use super::layers::Layer;
+use super::lru::{TEXTURE_BUDGET, TextureLru};
+
+/// Most room layers composited in a single frame, so panning onto lots of new rooms at once doesn't stall
+const MAX_COMPOSITES_PER_FRAME: usize = 16;
+
+/// A layer of a room, keyed by level ID, room ID and layer
+pub type RoomLayerKey = (i32, i32, Layer);
+
+/// Layers of rooms composited into their own textures, so each is drawn with one copy instead of one per tile
+pub struct RoomCache {
+    /// Every cached layer along with the tileset it was drawn from
+    layers: TextureLru<RoomLayerKey, String>,
+    texture_creator: TextureCreator<WindowContext>,
+    /// Whether the renderer can draw into textures at all
+    supported: bool,
+    composited_this_frame: usize,
+    /// How every cell of each room collides, keyed by level ID and room ID
+    collisions: HashMap<(i32, i32), CollisionGrid>,
+}
+
+impl RoomCache {
+    pub fn new(canvas: &Canvas<Window>) -> RoomCache {
+        RoomCache {
+            layers: TextureLru::new(TEXTURE_BUDGET),
+            texture_creator: canvas.texture_creator(),
+            supported: canvas.render_target_supported(),
+            composited_this_frame: 0,
+            collisions: HashMap::new(),
+        }
+    }
+
+    /// The texture of a layer of a room, drawing it with `composite` first if it isn't cached yet
+    ///
+    /// `composite` draws the layer with the room's top left corner at (0, 0). Returns `None` if the layer can't be
+    /// cached right now, in which case it should be drawn tile by tile instead.
+    pub fn layer(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        key: RoomLayerKey,
+        tileset: &str,
+        (width, height): (u32, u32),
+        composite: impl FnOnce(&mut Canvas<Window>),
+    ) -> Option<&mut Texture> {
+        if !self.layers.contains(&key) {
+            if !self.supported
+                || width == 0
+                || height == 0
+                || self.composited_this_frame >= MAX_COMPOSITES_PER_FRAME
+            {
+                return None;
+            }
+            self.composited_this_frame += 1;
+
+            let mut texture = match self.texture_creator.create_texture_target(
+                PixelFormatEnum::ARGB8888,
+                width,
+                height,
+            ) {
+                Ok(texture) => texture,
+                Err(err) => {
+                    eprintln!("couldn't cache a room layer, drawing tiles directly: {err}");
+                    self.supported = false;
+                    return None;
+                }
+            };
+            texture.set_blend_mode(BlendMode::Blend);
+            canvas
+                .with_texture_canvas(&mut texture, |target| {
+                    target.set_draw_color(Color::RGBA(0, 0, 0, 0));
+                    target.clear();
+                    composite(target);
+                })
+                .unwrap();
+            self.layers.insert(key, texture, tileset.to_owned());
+        }
+
+        self.layers.get_mut(&key)
+    }
+
+    /// How every cell of a room collides, worked out the first time it's drawn
+    pub fn collision(
+        &mut self,
+        level_id: i32,
+        room: &Room,
+        table: &CollisionTable,
+    ) -> &CollisionGrid {
+        self.collisions
+            .entry((level_id, room.id))
+            .or_insert_with(|| table.room(room))
+    }
+
+    /// Drops every layer and collision grid of a level's rooms, after the level changes
+    pub fn forget_level(&mut self, level_id: i32) {
+        self.layers.remove_where(|key, _| key.0 == level_id);
+        self.collisions.retain(|key, _| key.0 != level_id);
+    }
+
+    /// Drops every layer drawn from a tileset, after the tileset changes
+    pub fn forget_tileset(&mut self, tileset: &str) {
+        self.layers
+            .remove_where(|_, layer_tileset| layer_tileset == tileset);
+    }
+
+    /// Drops every layer and collision grid
+    pub fn clear(&mut self) {
+        self.layers.clear();
+        self.collisions.clear();
+    }
+
+    /// Drops the least recently drawn layers until they fit in the budget, keeping any drawn this frame
+    pub fn end_frame(&mut self) {
+        self.layers.end_frame();
+        self.composited_this_frame = 0;
+    }
+}
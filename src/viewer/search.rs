@@ -0,0 +1,55 @@
+use timespinner_map::world::World;
+
+use super::RoomKey;
+
+/// Most results shown at once
+pub const MAX_RESULTS: usize = 10;
+
+/// The jump-to-room search box, while it's open
+#[derive(Clone, Debug, Default)]
+pub struct Search {
+    pub query: String,
+    /// Matching rooms, best first, with the label they're shown with
+    pub results: Vec<(RoomKey, String)>,
+    /// Index of the highlighted result
+    pub selected: usize,
+}
+
+impl Search {
+    pub fn push(&mut self, world: &World, text: &str) {
+        self.query.push_str(text);
+        self.update(world);
+    }
+
+    pub fn pop(&mut self, world: &World) {
+        self.query.pop();
+        self.update(world);
+    }
+
+    /// Moves the highlight by `step` results, wrapping around
+    pub fn select(&mut self, step: isize) {
+        let len = self.results.len().min(MAX_RESULTS);
+        if len > 0 {
+            self.selected = (self.selected as isize + step).rem_euclid(len as isize) as usize;
+        }
+    }
+
+    pub fn selection(&self) -> Option<RoomKey> {
+        self.results.get(self.selected).map(|(key, _)| *key)
+    }
+
+    fn update(&mut self, world: &World) {
+        self.results = world
+            .search(&self.query)
+            .into_iter()
+            .map(|room| {
+                let label = format!(
+                    "{}:{} {} - {}",
+                    room.area.id, room.room.id, room.room.name, room.level.name
+                );
+                ((room.area.id, room.room.id), label)
+            })
+            .collect();
+        self.selected = 0;
+    }
+}
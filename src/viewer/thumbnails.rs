@@ -0,0 +1,101 @@
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{BlendMode, Texture, TextureCreator};
+use sdl2::video::WindowContext;
+use std::collections::HashSet;
+use timespinner_map::level::Room;
+use timespinner_map::render::Image;
+use timespinner_map::thumbnail::Thumbnails;
+
+use super::lru::{TEXTURE_BUDGET, TextureLru};
+
+/// Most thumbnails loaded or rendered in a single frame, so zooming out doesn't freeze the viewer
+const MAX_LOADS_PER_FRAME: usize = 8;
+
+/// Room thumbnails as textures, keyed by level and room ID
+pub struct ThumbnailTextures {
+    thumbnails: Thumbnails<'static>,
+    texture_creator: TextureCreator<WindowContext>,
+    textures: TextureLru<(i32, i32)>,
+    /// Rooms whose thumbnails couldn't be made, so they aren't tried again every frame
+    failed: HashSet<(i32, i32)>,
+    loaded_this_frame: usize,
+}
+
+impl ThumbnailTextures {
+    pub fn new(
+        thumbnails: Thumbnails<'static>,
+        texture_creator: TextureCreator<WindowContext>,
+    ) -> ThumbnailTextures {
+        ThumbnailTextures {
+            thumbnails,
+            texture_creator,
+            textures: TextureLru::new(TEXTURE_BUDGET),
+            failed: HashSet::new(),
+            loaded_this_frame: 0,
+        }
+    }
+
+    pub fn thumbnails_mut(&mut self) -> &mut Thumbnails<'static> {
+        &mut self.thumbnails
+    }
+
+    /// A room's thumbnail, or `None` if it isn't ready yet or couldn't be made
+    pub fn get(&mut self, level_id: i32, room: &Room) -> Option<&Texture> {
+        let key = (level_id, room.id);
+        if !self.textures.contains(&key) {
+            if self.failed.contains(&key) || self.loaded_this_frame >= MAX_LOADS_PER_FRAME {
+                return None;
+            }
+            self.loaded_this_frame += 1;
+            let texture = self
+                .thumbnails
+                .get(level_id, room)
+                .and_then(|image| self.create_texture(&image));
+            crate::print_warnings(&self.thumbnails.take_warnings());
+            match texture {
+                Ok(texture) => {
+                    self.textures.insert(key, texture, ());
+                }
+                Err(err) => {
+                    eprintln!(
+                        "couldn't make thumbnail of room {level_id}:{}: {err:#}",
+                        room.id
+                    );
+                    self.failed.insert(key);
+                    return None;
+                }
+            }
+        }
+        self.textures.get_mut(&key).map(|texture| &*texture)
+    }
+
+    fn create_texture(&self, image: &Image) -> anyhow::Result<Texture> {
+        let mut texture = self.texture_creator.create_texture_static(
+            PixelFormatEnum::RGBA32,
+            image.width,
+            image.height,
+        )?;
+        texture.update(None, &image.pixels, image.width as usize * 4)?;
+        texture.set_blend_mode(BlendMode::Blend);
+        Ok(texture)
+    }
+
+    /// Forgets the thumbnails of a level, so they're made again the next time they're drawn
+    pub fn forget_level(&mut self, level_id: i32) {
+        self.textures
+            .remove_where(|(other, _), _| *other == level_id);
+        self.failed.retain(|(other, _)| *other != level_id);
+    }
+
+    /// Forgets every thumbnail
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.failed.clear();
+    }
+
+    /// Destroys the least recently drawn thumbnails until they fit in the budget, keeping any drawn this frame
+    pub fn end_frame(&mut self) {
+        self.textures.end_frame();
+        self.loaded_this_frame = 0;
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the watched directories are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Notices files changing by checking when they were last modified every so often
+#[derive(Debug)]
+pub struct Watcher {
+    /// Directories to watch, and whether to look inside their subdirectories
+    directories: Vec<(PathBuf, bool)>,
+    modified: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+}
+
+impl Watcher {
+    pub fn new(directories: Vec<(PathBuf, bool)>) -> Watcher {
+        let mut watcher = Watcher {
+            directories,
+            modified: HashMap::new(),
+            last_poll: Instant::now(),
+        };
+        watcher.modified = watcher.scan();
+        watcher
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut modified = HashMap::new();
+        for (directory, recursive) in &self.directories {
+            scan_directory(directory, *recursive, &mut modified);
+        }
+        modified
+    }
+
+    /// Files that were added or modified since the last time this found any, checking at most once every
+    /// [`POLL_INTERVAL`]
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let modified = self.scan();
+        let changed = modified
+            .iter()
+            .filter(|(path, time)| self.modified.get(*path) != Some(time))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.modified = modified;
+        changed
+    }
+}
+
+fn scan_directory(directory: &Path, recursive: bool, modified: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if recursive {
+                scan_directory(&entry.path(), recursive, modified);
+            }
+        } else if let Ok(time) = metadata.modified() {
+            modified.insert(entry.path(), time);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::geometry::Rect;
+use crate::render::{Image, Renderer};
+use crate::world::World;
+
+/// Width and height of every image in the pyramid
+pub const TILE_SIZE: u32 = 256;
+
+const VIEWER: &str = include_str!("web_map.html");
+
+/// Renders the whole world as a slippy map tile pyramid into `out_dir`, along with an `index.html` to browse it
+///
+/// Tiles are written to `tiles/{z}/{x}/{y}.png`, where the highest zoom level is drawn at one pixel per game pixel and
+/// each level below it is half the size of the one above. Tiles without any room in them are skipped. `progress` is
+/// called with each zoom level as it starts being rendered, from the highest down. Returns the highest zoom level.
+pub fn export_web_map<T, F>(
+    world: &World,
+    renderer: &mut Renderer,
+    out_dir: T,
+    mut progress: F,
+) -> Result<u32>
+where
+    T: AsRef<Path>,
+    F: FnMut(u32),
+{
+    let out_dir = out_dir.as_ref();
+    let bounds = world
+        .bounds()
+        .ok_or_else(|| anyhow!("no rooms to render"))?;
+
+    let mut columns = bounds.width().div_ceil(TILE_SIZE);
+    let mut rows = bounds.height().div_ceil(TILE_SIZE);
+    let max_zoom = columns.max(rows).next_power_of_two().ilog2();
+
+    progress(max_zoom);
+    let mut written = HashSet::new();
+    for y in 0..rows {
+        for x in 0..columns {
+            let tile_rect = Rect::new(
+                bounds.x() + (x * TILE_SIZE) as i32,
+                bounds.y() + (y * TILE_SIZE) as i32,
+                TILE_SIZE,
+                TILE_SIZE,
+            );
+
+            let mut image = Image::new(TILE_SIZE, TILE_SIZE);
+            let mut empty = true;
+            for room in world.rooms() {
+                let room_rect = room.bounds();
+                if !room_rect.has_intersection(tile_rect) {
+                    continue;
+                }
+                renderer.draw_room(
+                    &mut image,
+                    room.room,
+                    room_rect.x() - tile_rect.x(),
+                    room_rect.y() - tile_rect.y(),
+                )?;
+                empty = false;
+            }
+
+            if !empty {
+                save_tile(out_dir, max_zoom, x, y, &image)?;
+                written.insert((x, y));
+            }
+        }
+    }
+
+    for zoom in (0..max_zoom).rev() {
+        progress(zoom);
+        columns = columns.div_ceil(2);
+        rows = rows.div_ceil(2);
+
+        let mut parents = HashSet::new();
+        for y in 0..rows {
+            for x in 0..columns {
+                let mut image = Image::new(TILE_SIZE, TILE_SIZE);
+                let mut empty = true;
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let child = (x * 2 + dx, y * 2 + dy);
+                    if !written.contains(&child) {
+                        continue;
+                    }
+                    let path = tile_path(out_dir, zoom + 1, child.0, child.1);
+                    let half = Image::load_png(path)?.half_size();
+                    image.blit(
+                        &half,
+                        Rect::new(0, 0, half.width, half.height),
+                        (dx * TILE_SIZE / 2) as i32,
+                        (dy * TILE_SIZE / 2) as i32,
+                        false,
+                        false,
+                    );
+                    empty = false;
+                }
+
+                if !empty {
+                    save_tile(out_dir, zoom, x, y, &image)?;
+                    parents.insert((x, y));
+                }
+            }
+        }
+        written = parents;
+    }
+
+    let html = VIEWER
+        .replace("{{MAX_ZOOM}}", &max_zoom.to_string())
+        .replace("{{TILE_SIZE}}", &TILE_SIZE.to_string())
+        .replace("{{WIDTH}}", &bounds.width().to_string())
+        .replace("{{HEIGHT}}", &bounds.height().to_string());
+    fs::write(out_dir.join("index.html"), html)?;
+
+    Ok(max_zoom)
+}
+
+fn tile_path(out_dir: &Path, zoom: u32, x: u32, y: u32) -> PathBuf {
+    out_dir
+        .join("tiles")
+        .join(zoom.to_string())
+        .join(x.to_string())
+        .join(format!("{y}.png"))
+}
+
+fn save_tile(out_dir: &Path, zoom: u32, x: u32, y: u32, image: &Image) -> Result<()> {
+    let path = tile_path(out_dir, zoom, x, y);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    image.save_png(path)
+}
@@ -0,0 +1,431 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::era::{Era, EraTable, classify_eras};
+use crate::geometry::Rect;
+use crate::level::{self, Level};
+use crate::minimap::{self, Area, Minimap};
+use crate::{TILE_SIZE, load_map_from_file};
+
+/// Width of a screen, the size of a minimap cell, in tiles
+pub const ROOM_WIDTH: i32 = 25;
+/// Height of a screen, the size of a minimap cell, in tiles
+pub const ROOM_HEIGHT: i32 = 20;
+
+/// How many screens across and down a room of the given size in tiles takes up
+///
+/// Rooms that aren't a whole number of screens in size still take up the screen they only partly cover.
+pub fn screens(width: u32, height: u32) -> (u32, u32) {
+    (
+        width.div_ceil(ROOM_WIDTH as u32).max(1),
+        height.div_ceil(ROOM_HEIGHT as u32).max(1),
+    )
+}
+
+/// Position of a minimap cell's top left corner in pixels
+pub fn cell_origin(x: i32, y: i32) -> (i32, i32) {
+    (x * TILE_SIZE * ROOM_WIDTH, y * TILE_SIZE * ROOM_HEIGHT)
+}
+
+/// The minimap along with every level it references
+#[derive(Debug)]
+pub struct World {
+    pub minimap: Minimap,
+    /// Every level file, keyed by the level's ID, which is the ID of the minimap area it belongs to
+    pub levels: HashMap<i32, Level>,
+    /// File every level was loaded from, keyed by the level's ID
+    pub level_paths: HashMap<i32, PathBuf>,
+    /// Era of every area, keyed by area ID
+    pub eras: HashMap<i32, Era>,
+    /// Why each level file that was skipped while loading couldn't be used
+    pub skipped_levels: Vec<String>,
+}
+
+/// A room from a level along with where the minimap places it
+#[derive(Clone, Copy, Debug)]
+pub struct PlacedRoom<'a> {
+    pub area: &'a Area,
+    pub era: Era,
+    pub level: &'a Level,
+    pub placement: &'a minimap::Room,
+    pub room: &'a level::Room,
+}
+
+/// Every level file in `Content/Levels`, which is every `.dat` file apart from the minimap
+fn level_files<T: AsRef<Path>>(timespinner_path: T) -> Result<Vec<PathBuf>> {
+    let mut directory = timespinner_path.as_ref().to_owned();
+    directory.extend(["Content", "Levels"]);
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&directory)
+        .with_context(|| format!("couldn't read levels from {directory:?}"))?
+    {
+        let path = entry?.path();
+        let is_level = path.extension().is_some_and(|extension| extension == "dat")
+            && path.file_name().is_some_and(|name| name != "Minimap.dat");
+        if is_level {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+impl World {
+    pub fn load<T: AsRef<Path>>(timespinner_path: T) -> Result<World> {
+        World::load_with_progress(timespinner_path, |_, _| ())
+    }
+
+    /// Loads the minimap and every level file in `Content/Levels`, parsing the levels in parallel
+    ///
+    /// Levels are matched up with minimap areas by the ID stored in the level file, so levels don't need any particular
+    /// file name, and level files that can't be parsed are skipped instead of failing the whole load. Nothing is
+    /// printed, so show [`World::warnings`] afterwards. `progress` is called with how many levels have been loaded and
+    /// how many there are, each time one finishes.
+    pub fn load_with_progress<T, F>(timespinner_path: T, progress: F) -> Result<World>
+    where
+        T: AsRef<Path>,
+        F: Fn(usize, usize) + Sync,
+    {
+        let minimap = Minimap::load(&timespinner_path)?;
+        let paths = level_files(&timespinner_path)?;
+
+        let done = AtomicUsize::new(0);
+        progress(0, paths.len());
+        let results: Vec<Result<Level>> = thread::scope(|scope| {
+            let handles: Vec<_> = paths
+                .iter()
+                .map(|path| {
+                    let (done, progress, total) = (&done, &progress, paths.len());
+                    scope.spawn(move || {
+                        let level = load_map_from_file(path)
+                            .with_context(|| format!("couldn't load level from {path:?}"));
+                        progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+                        level
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        let mut levels = HashMap::new();
+        let mut level_paths = HashMap::new();
+        let mut skipped_levels = Vec::new();
+        for (path, level) in paths.into_iter().zip(results) {
+            let level = match level {
+                Ok(level) => level,
+                Err(err) => {
+                    skipped_levels.push(format!("{err:#}, skipping it"));
+                    continue;
+                }
+            };
+            if let Some(other) = level_paths.get(&level.id) {
+                skipped_levels.push(format!(
+                    "{path:?} has the same level ID ({}) as {other:?}, skipping it",
+                    level.id
+                ));
+                continue;
+            }
+            level_paths.insert(level.id, path);
+            levels.insert(level.id, level);
+        }
+
+        let eras = classify_eras(&minimap, &EraTable::default());
+        Ok(World {
+            minimap,
+            levels,
+            level_paths,
+            eras,
+            skipped_levels,
+        })
+    }
+
+    /// Everything worth warning about once the world is loaded
+    ///
+    /// These are the level files that were skipped, levels that aren't on the minimap, rooms that aren't placed on it
+    /// and minimap areas without a level. The library doesn't print anything itself, so it's up to the caller to show
+    /// them.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.skipped_levels.clone();
+        for level in self.unreferenced_levels() {
+            warnings.push(format!(
+                "level {} ({}) in {:?} isn't on the minimap",
+                level.id, level.name, self.level_paths[&level.id]
+            ));
+        }
+        for (level, rooms) in self.unplaced_rooms() {
+            let ids: Vec<String> = rooms.iter().map(|room| room.id.to_string()).collect();
+            warnings.push(format!(
+                "level {} ({}) has rooms that aren't on the minimap: {}",
+                level.id,
+                level.name,
+                ids.join(", ")
+            ));
+        }
+        for area_id in self.missing_levels() {
+            warnings.push(format!(
+                "no level file has the ID of minimap area {area_id}"
+            ));
+        }
+        warnings
+    }
+
+    /// Path of the file a level was loaded from
+    pub fn level_path(&self, level_id: i32) -> Option<&Path> {
+        self.level_paths.get(&level_id).map(PathBuf::as_path)
+    }
+
+    /// Loads a level file again, or for the first time if it's new, keeping the old level if it can't be loaded
+    ///
+    /// Returns the ID of the level in the file.
+    pub fn reload_level<T: AsRef<Path>>(&mut self, path: T) -> Result<i32> {
+        let path = path.as_ref();
+        let level = load_map_from_file(path)?;
+        if let Some(old_id) = self
+            .level_paths
+            .iter()
+            .find(|(_, other)| *other == path)
+            .map(|(id, _)| *id)
+        {
+            self.level_paths.remove(&old_id);
+            self.levels.remove(&old_id);
+        }
+        let id = level.id;
+        self.level_paths.insert(id, path.to_owned());
+        self.levels.insert(id, level);
+        Ok(id)
+    }
+
+    /// Levels whose ID doesn't match any area on the minimap, sorted by ID
+    pub fn unreferenced_levels(&self) -> Vec<&Level> {
+        let mut levels: Vec<&Level> = self
+            .levels
+            .values()
+            .filter(|level| {
+                !self
+                    .minimap
+                    .area_list
+                    .areas
+                    .iter()
+                    .any(|area| area.id == level.id)
+            })
+            .collect();
+        levels.sort_by_key(|level| level.id);
+        levels
+    }
+
+    /// Rooms of levels on the minimap that the minimap doesn't place anywhere, grouped by level and sorted by level ID
+    pub fn unplaced_rooms(&self) -> Vec<(&Level, Vec<&level::Room>)> {
+        let mut unplaced = Vec::new();
+        for area in &self.minimap.area_list.areas {
+            let Some(level) = self.levels.get(&area.id) else {
+                continue;
+            };
+            let rooms: Vec<&level::Room> = level
+                .rooms
+                .iter()
+                .filter(|room| {
+                    !area
+                        .room_list
+                        .rooms
+                        .iter()
+                        .any(|placement| placement.id == room.id)
+                })
+                .collect();
+            if !rooms.is_empty() {
+                unplaced.push((level, rooms));
+            }
+        }
+        unplaced.sort_by_key(|(level, _)| level.id);
+        unplaced
+    }
+
+    /// IDs of minimap areas that don't have a level
+    pub fn missing_levels(&self) -> Vec<i32> {
+        self.minimap
+            .area_list
+            .areas
+            .iter()
+            .map(|area| area.id)
+            .filter(|id| !self.levels.contains_key(id))
+            .collect()
+    }
+
+    /// Every room that is both listed on the minimap and present in its level
+    pub fn rooms(&self) -> impl Iterator<Item = PlacedRoom<'_>> {
+        self.minimap.area_list.areas.iter().flat_map(move |area| {
+            let level = self.levels.get(&area.id);
+            let era = self.era(area.id);
+            area.room_list.rooms.iter().filter_map(move |placement| {
+                let level = level?;
+                let room = level.rooms.iter().find(|room| room.id == placement.id)?;
+                Some(PlacedRoom {
+                    area,
+                    era,
+                    level,
+                    placement,
+                    room,
+                })
+            })
+        })
+    }
+
+    /// Looks up a placed room by the ID of its area and its own ID
+    pub fn room(&self, area_id: i32, room_id: i32) -> Option<PlacedRoom<'_>> {
+        self.rooms()
+            .find(|room| room.area.id == area_id && room.room.id == room_id)
+    }
+
+    /// Finds placed rooms matching a search query, best matches first
+    ///
+    /// `AREA:ROOM` matches a single room, a number matches rooms or areas with that ID, and anything else matches room
+    /// and level names containing it, ignoring case.
+    pub fn search(&self, query: &str) -> Vec<PlacedRoom<'_>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let key = query
+            .split_once(':')
+            .and_then(|(area, room)| Some((area.trim().parse().ok()?, room.trim().parse().ok()?)));
+        let id: Option<i32> = query.parse().ok();
+
+        // lower scores come first
+        let score = |room: &PlacedRoom| {
+            if key == Some((room.area.id, room.room.id)) {
+                Some(0)
+            } else if key.is_some() {
+                None
+            } else if id == Some(room.room.id) {
+                Some(1)
+            } else if id == Some(room.area.id) {
+                Some(2)
+            } else if room.room.name.to_lowercase().contains(&query) {
+                Some(3)
+            } else if room.level.name.to_lowercase().contains(&query) {
+                Some(4)
+            } else {
+                None
+            }
+        };
+
+        let mut matches: Vec<(i32, PlacedRoom)> = self
+            .rooms()
+            .filter_map(|room| Some((score(&room)?, room)))
+            .collect();
+        matches.sort_by_key(|(score, room)| (*score, room.area.id, room.room.id));
+        matches.into_iter().map(|(_, room)| room).collect()
+    }
+
+    /// Works out the era of every area again, taking the ones `table` lists from it instead of guessing
+    ///
+    /// Loading only guesses eras from the minimap's layout, see [`classify_eras`].
+    pub fn set_era_table(&mut self, table: &EraTable) {
+        self.eras = classify_eras(&self.minimap, table);
+    }
+
+    /// Era of an area, areas that couldn't be classified are treated as being in the present
+    pub fn era(&self, area_id: i32) -> Era {
+        self.eras.get(&area_id).copied().unwrap_or(Era::Present)
+    }
+
+    /// Smallest rectangle containing every placed room, in pixels
+    pub fn bounds(&self) -> Option<Rect> {
+        self.rooms()
+            .map(|room| room.bounds())
+            .reduce(|bounds, room| bounds.union(room))
+    }
+
+    /// Smallest rectangle containing every placed room of an era, in pixels
+    pub fn era_bounds(&self, era: Era) -> Option<Rect> {
+        self.rooms()
+            .filter(|room| room.era == era)
+            .map(|room| room.bounds())
+            .reduce(|bounds, room| bounds.union(room))
+    }
+
+    /// Smallest rectangle containing every placed room of an area, in pixels
+    pub fn area_bounds(&self, area_id: i32) -> Option<Rect> {
+        self.rooms()
+            .filter(|room| room.area.id == area_id)
+            .map(|room| room.bounds())
+            .reduce(|bounds, room| bounds.union(room))
+    }
+}
+
+/// Two placed rooms whose tiles cover some of the same space
+#[derive(Clone, Copy, Debug)]
+pub struct Overlap<'a> {
+    pub a: PlacedRoom<'a>,
+    pub b: PlacedRoom<'a>,
+    /// The space both rooms cover, in pixels
+    pub area: Rect,
+}
+
+impl World {
+    /// Every pair of placed rooms that cover some of the same space
+    pub fn overlaps(&self) -> Vec<Overlap<'_>> {
+        let mut rooms: Vec<(Rect, PlacedRoom)> =
+            self.rooms().map(|room| (room.bounds(), room)).collect();
+        rooms.sort_by_key(|(bounds, _)| bounds.x());
+
+        let mut overlaps = Vec::new();
+        for (i, (a_bounds, a)) in rooms.iter().enumerate() {
+            // rooms are sorted by where they start, so nothing after a room starting past this one's right edge can hit it
+            for (b_bounds, b) in rooms[i + 1..]
+                .iter()
+                .take_while(|(b_bounds, _)| b_bounds.x() < a_bounds.right())
+            {
+                if let Some(area) = a_bounds.intersection(*b_bounds) {
+                    overlaps.push(Overlap { a: *a, b: *b, area });
+                }
+            }
+        }
+        overlaps
+    }
+}
+
+impl PlacedRoom<'_> {
+    /// Position of the room's top left corner in pixels, which is the top left of its first minimap cell
+    pub fn origin(&self) -> (i32, i32) {
+        cell_origin(self.placement.position.x, self.placement.position.y)
+    }
+
+    /// How many screens across and down the room's tiles take up
+    pub fn screens(&self) -> (u32, u32) {
+        screens(self.room.width, self.room.height)
+    }
+
+    /// Area the minimap says the room covers in pixels, which might not match the room's tiles
+    pub fn minimap_bounds(&self) -> Rect {
+        let (x, y) = self.origin();
+        Rect::new(
+            x,
+            y,
+            self.placement.width * (TILE_SIZE * ROOM_WIDTH) as u32,
+            self.placement.height * (TILE_SIZE * ROOM_HEIGHT) as u32,
+        )
+    }
+
+    /// Whether the minimap covers as many screens as the room's tiles do
+    pub fn matches_minimap(&self) -> bool {
+        self.screens() == (self.placement.width, self.placement.height)
+    }
+
+    /// Area the room's tiles cover in pixels
+    pub fn bounds(&self) -> Rect {
+        let (x, y) = self.origin();
+        Rect::new(
+            x,
+            y,
+            self.room.width * TILE_SIZE as u32,
+            self.room.height * TILE_SIZE as u32,
+        )
+    }
+}